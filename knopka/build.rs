@@ -5,4 +5,5 @@ fn main() {
         &["world.slang"],
     );
     nechto_build::build_scripts("../data/engine/script", "../build/engine/script");
+    nechto_build::build_type_declarations("../nechto/src/js/types", "../build/script/nechto.d.ts");
 }
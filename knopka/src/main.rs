@@ -1,12 +1,66 @@
-use nechto::runtime::{App, Runtime};
+use nechto::event::ActionPerformed;
+use nechto::realm::{Realm, Scene, SceneComponents};
+use nechto::runtime::{App, Resources, Runtime};
+use nechto::vfs::{Permissions, VirtualFs};
+use tracing::{error, info};
 
-pub struct Editor {}
+/// Scene edited by default, saved with the `save_scene` action.
+const SCENE: &str = "$scenes/main.scene";
 
-impl App for Editor {}
+pub struct Editor {
+    realm: Realm,
+    components: SceneComponents,
+}
+
+impl App for Editor {
+    fn configure_vfs(&mut self, vfs: &mut VirtualFs) {
+        // Scenes are saved into the data directory they are loaded from, the rest of the data
+        // stays read-only.
+        vfs.add_search_path_with_permissions(
+            "$scenes",
+            "data/scenes".into(),
+            Permissions::READ_WRITE,
+        );
+    }
+
+    fn init(&mut self, resources: &mut Resources) {
+        if !resources.vfs.exists(SCENE).unwrap_or(false) {
+            return;
+        }
+
+        match Scene::load(&resources.vfs, SCENE).and_then(|scene| scene.to_realm(&self.components))
+        {
+            Ok(realm) => self.realm = realm,
+            Err(err) => error!("unable to load scene {}: {}", SCENE, err),
+        }
+    }
+
+    fn update(&mut self, resources: &mut Resources) {
+        self.realm.update_transforms();
+
+        let save = resources
+            .events
+            .read::<ActionPerformed>()
+            .any(|event| event.action == "save_scene");
+
+        if save {
+            let result = Scene::from_realm(&self.realm, &self.components)
+                .and_then(|scene| scene.save(&resources.vfs, SCENE));
+
+            match result {
+                Ok(()) => info!("scene saved to {}", SCENE),
+                Err(err) => error!("unable to save scene {}: {}", SCENE, err),
+            }
+        }
+    }
+}
 
 fn main() {
     tracing_subscriber::fmt::init();
 
-    let rt = Runtime::new(Editor {});
+    let rt = Runtime::new(Editor {
+        realm: Realm::new(),
+        components: SceneComponents::new(),
+    });
     rt.run();
 }
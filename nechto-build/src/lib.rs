@@ -1,4 +1,4 @@
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 
 pub fn build_shaders(source_prefix: &str, output_prefix: &str, paths: &[&str]) {
@@ -54,6 +54,54 @@ pub fn build_scripts(source_dir: impl AsRef<Path>, output_dir: impl AsRef<Path>)
     }
 }
 
+/// Paths of the `.d.ts` files in `source_dir`, sorted by name.
+///
+/// This is the list of declarations both for `build_type_declarations` and for the
+/// `TYPE_DECLARATIONS` nechto embeds, so the two can't drift apart.
+pub fn type_declaration_paths(source_dir: impl AsRef<Path>) -> Vec<PathBuf> {
+    println!("cargo::rerun-if-changed={}", source_dir.as_ref().display());
+
+    let mut paths: Vec<_> = std::fs::read_dir(source_dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.to_str().is_some_and(|path| path.ends_with(".d.ts")))
+        .collect();
+
+    paths.sort();
+    paths
+}
+
+/// Concatenates the `.d.ts` files in `source_dir` into a single declaration file at `output_path`.
+pub fn build_type_declarations(source_dir: impl AsRef<Path>, output_path: impl AsRef<Path>) {
+    let mut output =
+        String::from("// Generated by nechto-build from the engine's native bindings.\n");
+
+    for path in type_declaration_paths(source_dir) {
+        output.push('\n');
+        output.push_str(&std::fs::read_to_string(path).unwrap());
+    }
+
+    if let Some(parent) = output_path.as_ref().parent() {
+        std::fs::create_dir_all(parent).unwrap();
+    }
+
+    std::fs::write(output_path, output).unwrap();
+}
+
+/// Writes a Rust slice expression that includes the `.d.ts` files in `source_dir` as strings.
+pub fn build_type_declaration_list(source_dir: impl AsRef<Path>, output_path: impl AsRef<Path>) {
+    let mut output = String::from("&[\n");
+
+    for path in type_declaration_paths(source_dir) {
+        let path = std::fs::canonicalize(path).unwrap();
+        output.push_str(&format!("    include_str!({:?}),\n", path));
+    }
+
+    output.push_str("]\n");
+
+    std::fs::write(output_path, output).unwrap();
+}
+
 fn print_text_as_error(text: &str) {
     for line in text.lines() {
         println!("cargo::error={}", line);
@@ -0,0 +1,117 @@
+//! Compares `Pool` and `DensePool`, run with `cargo bench -p nechto --bench pool`.
+
+use std::hint::black_box;
+use std::time::Instant;
+
+use nechto::collections::{DensePool, Pool};
+use nechto::handle::Handle;
+
+const COUNT: usize = 10_000;
+const ITERATIONS: u32 = 200;
+
+#[derive(Clone, Copy)]
+struct Particle {
+    position: [f32; 3],
+    velocity: [f32; 3],
+}
+
+impl Particle {
+    fn new(index: usize) -> Self {
+        let value = index as f32;
+
+        Self {
+            position: [value, value, value],
+            velocity: [1.0, 0.5, 0.25],
+        }
+    }
+
+    fn step(&mut self) {
+        for axis in 0..3 {
+            self.position[axis] += self.velocity[axis];
+        }
+    }
+}
+
+fn bench(name: &str, mut run: impl FnMut()) {
+    run();
+
+    let start = Instant::now();
+
+    for _ in 0..ITERATIONS {
+        run();
+    }
+
+    let average = start.elapsed() / ITERATIONS;
+    println!("{:<40} {:>12?}", name, average);
+}
+
+/// Fills a pool and removes every third value, so that the sparse pool has holes.
+macro_rules! fragmented {
+    ($pool:ty) => {{
+        let mut pool = <$pool>::new();
+        let handles: Vec<Handle<Particle>> = (0..COUNT)
+            .map(|index| pool.insert(Particle::new(index)))
+            .collect();
+
+        for handle in handles.iter().step_by(3) {
+            pool.remove(*handle);
+        }
+
+        let handles: Vec<Handle<Particle>> = pool.handles().collect();
+        (pool, handles)
+    }};
+}
+
+fn main() {
+    println!(
+        "{} values, every third removed, average of {} runs",
+        COUNT, ITERATIONS
+    );
+
+    bench("Pool insert", || {
+        let mut pool = Pool::new();
+        for index in 0..COUNT {
+            black_box(pool.insert(Particle::new(index)));
+        }
+    });
+
+    bench("DensePool insert", || {
+        let mut pool = DensePool::new();
+        for index in 0..COUNT {
+            black_box(pool.insert(Particle::new(index)));
+        }
+    });
+
+    let (mut pool, handles) = fragmented!(Pool<Particle>);
+    let (mut dense, dense_handles) = fragmented!(DensePool<Particle>);
+
+    bench("Pool iter_mut", || {
+        for (_, particle) in pool.iter_mut() {
+            particle.step();
+        }
+    });
+
+    bench("DensePool iter_mut", || {
+        for (_, particle) in dense.iter_mut() {
+            particle.step();
+        }
+    });
+
+    bench("DensePool values_mut", || {
+        for particle in dense.values_mut() {
+            particle.step();
+        }
+    });
+
+    bench("Pool get", || {
+        for handle in &handles {
+            black_box(pool.get(*handle));
+        }
+    });
+
+    bench("DensePool get", || {
+        for handle in &dense_handles {
+            black_box(dense.get(*handle));
+        }
+    });
+}
@@ -0,0 +1,10 @@
+use std::path::Path;
+
+fn main() {
+    let out_dir = std::env::var("OUT_DIR").unwrap();
+
+    nechto_build::build_type_declaration_list(
+        "src/js/types",
+        Path::new(&out_dir).join("type_declarations.rs"),
+    );
+}
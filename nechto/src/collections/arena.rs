@@ -0,0 +1,338 @@
+use std::alloc::{self, Layout};
+use std::cell::{Cell, RefCell};
+use std::marker::PhantomData;
+use std::ptr::NonNull;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Alignment of chunks, the largest alignment of values that can be allocated.
+const CHUNK_ALIGN: usize = 16;
+const MIN_CHUNK_CAPACITY: usize = 64 * 1024;
+
+/// Frame numbers are unique among all arenas, so that a `FrameSlice` never resolves in another
+/// arena or frame.
+static NEXT_FRAME: AtomicU64 = AtomicU64::new(1);
+
+struct Chunk {
+    data: NonNull<u8>,
+    capacity: usize,
+}
+
+impl Chunk {
+    fn new(capacity: usize) -> Self {
+        let layout = Layout::from_size_align(capacity, CHUNK_ALIGN).unwrap();
+        let data = unsafe { alloc::alloc(layout) };
+
+        Self {
+            data: NonNull::new(data).unwrap_or_else(|| alloc::handle_alloc_error(layout)),
+            capacity,
+        }
+    }
+}
+
+impl Drop for Chunk {
+    fn drop(&mut self) {
+        let layout = Layout::from_size_align(self.capacity, CHUNK_ALIGN).unwrap();
+        unsafe { alloc::dealloc(self.data.as_ptr(), layout) };
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ArenaStats {
+    /// Bytes allocated since the last reset, including alignment padding.
+    pub used: usize,
+    pub allocations: usize,
+    /// Bytes of all chunks.
+    pub capacity: usize,
+    /// Most bytes used in one frame.
+    pub peak: usize,
+}
+
+/// A linear allocator for data that lives until the end of a frame.
+///
+/// Allocating only moves an offset in the current chunk and allocates a larger chunk when it is
+/// full. Values are never dropped, so only `Copy` values can be allocated. Resetting frees
+/// everything at once and merges the chunks, so that the next frame of the same size fits in one.
+pub struct FrameArena {
+    chunks: RefCell<Vec<Chunk>>,
+    /// Offset of the free space in the last chunk.
+    offset: Cell<usize>,
+    used: Cell<usize>,
+    allocations: Cell<usize>,
+    peak: usize,
+    frame: u64,
+}
+
+impl FrameArena {
+    pub fn new() -> Self {
+        Self {
+            chunks: RefCell::new(Vec::new()),
+            offset: Cell::new(0),
+            used: Cell::new(0),
+            allocations: Cell::new(0),
+            peak: 0,
+            frame: NEXT_FRAME.fetch_add(1, Ordering::Relaxed),
+        }
+    }
+
+    /// Returns the chunk index and offset of free memory for the layout.
+    fn alloc_layout(&self, layout: Layout) -> (usize, usize) {
+        assert!(
+            layout.align() <= CHUNK_ALIGN,
+            "frame arena can't allocate values aligned to {} bytes",
+            layout.align()
+        );
+
+        let mut chunks = self.chunks.borrow_mut();
+        let offset = self.offset.get();
+        let start = offset.next_multiple_of(layout.align());
+
+        self.allocations.set(self.allocations.get() + 1);
+
+        if let Some(chunk) = chunks.last()
+            && start + layout.size() <= chunk.capacity
+        {
+            self.offset.set(start + layout.size());
+            self.used
+                .set(self.used.get() + start + layout.size() - offset);
+            return (chunks.len() - 1, start);
+        }
+
+        let last_capacity = chunks.last().map_or(0, |chunk| chunk.capacity);
+        let capacity = (last_capacity * 2)
+            .max(layout.size().next_multiple_of(CHUNK_ALIGN))
+            .max(MIN_CHUNK_CAPACITY);
+
+        chunks.push(Chunk::new(capacity));
+        self.offset.set(layout.size());
+        self.used.set(self.used.get() + layout.size());
+
+        (chunks.len() - 1, 0)
+    }
+
+    fn pointer<T>(&self, chunk: usize, offset: usize) -> NonNull<T> {
+        let chunks = self.chunks.borrow();
+        // Chunk memory is never moved or freed while `self` is borrowed.
+        unsafe { chunks[chunk].data.add(offset).cast() }
+    }
+
+    /// Allocates `len` values created by `value` and returns their location.
+    fn alloc_slice_with<T: Copy>(
+        &self,
+        len: usize,
+        mut value: impl FnMut(usize) -> T,
+    ) -> (usize, usize, NonNull<T>) {
+        if len == 0 || size_of::<T>() == 0 {
+            return (0, 0, NonNull::dangling());
+        }
+
+        let layout = Layout::array::<T>(len).unwrap();
+        let (chunk, offset) = self.alloc_layout(layout);
+        let pointer = self.pointer::<T>(chunk, offset);
+
+        for index in 0..len {
+            unsafe { pointer.add(index).write(value(index)) };
+        }
+
+        (chunk, offset, pointer)
+    }
+
+    pub fn alloc<T: Copy>(&self, value: T) -> &mut T {
+        &mut self.alloc_slice_fill_with(1, |_| value)[0]
+    }
+
+    pub fn alloc_slice_copy<T: Copy>(&self, values: &[T]) -> &mut [T] {
+        self.alloc_slice_fill_with(values.len(), |index| values[index])
+    }
+
+    #[allow(clippy::mut_from_ref)]
+    pub fn alloc_slice_fill_with<T: Copy>(
+        &self,
+        len: usize,
+        value: impl FnMut(usize) -> T,
+    ) -> &mut [T] {
+        let (_, _, pointer) = self.alloc_slice_with(len, value);
+
+        // The memory was just allocated, it isn't aliased until the arena is reset.
+        unsafe { std::slice::from_raw_parts_mut(pointer.as_ptr(), len) }
+    }
+
+    #[allow(clippy::mut_from_ref)]
+    pub fn alloc_str(&self, text: &str) -> &mut str {
+        let bytes = self.alloc_slice_copy(text.as_bytes());
+
+        unsafe { std::str::from_utf8_unchecked_mut(bytes) }
+    }
+
+    /// Allocates values that can be read back through the returned slice until the arena is
+    /// reset, including from the frame after with `FrameArenas::previous`.
+    pub fn push_slice<T: Copy>(&self, values: &[T]) -> FrameSlice<T> {
+        let (chunk, offset, _) = self.alloc_slice_with(values.len(), |index| values[index]);
+
+        FrameSlice {
+            frame: self.frame,
+            chunk,
+            offset,
+            len: values.len(),
+            _pd: PhantomData,
+        }
+    }
+
+    /// Returns the values of a slice pushed since the last reset, or `None` for other slices.
+    pub fn slice<T: Copy>(&self, slice: FrameSlice<T>) -> Option<&[T]> {
+        if slice.frame != self.frame {
+            return None;
+        }
+
+        if slice.len == 0 || size_of::<T>() == 0 {
+            return Some(unsafe {
+                std::slice::from_raw_parts(NonNull::dangling().as_ptr(), slice.len)
+            });
+        }
+
+        let pointer = self.pointer::<T>(slice.chunk, slice.offset);
+
+        Some(unsafe { std::slice::from_raw_parts(pointer.as_ptr(), slice.len) })
+    }
+
+    /// Frees all allocations.
+    pub fn reset(&mut self) {
+        let chunks = self.chunks.get_mut();
+
+        if chunks.len() > 1 {
+            let capacity = chunks.iter().map(|chunk| chunk.capacity).sum();
+            *chunks = vec![Chunk::new(capacity)];
+        }
+
+        self.peak = self.peak.max(*self.used.get_mut());
+        self.offset.set(0);
+        self.used.set(0);
+        self.allocations.set(0);
+        self.frame = NEXT_FRAME.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn stats(&self) -> ArenaStats {
+        ArenaStats {
+            used: self.used.get(),
+            allocations: self.allocations.get(),
+            capacity: self
+                .chunks
+                .borrow()
+                .iter()
+                .map(|chunk| chunk.capacity)
+                .sum(),
+            peak: self.peak.max(self.used.get()),
+        }
+    }
+}
+
+/// Location of values pushed into a `FrameArena`, valid until the arena is reset.
+pub struct FrameSlice<T> {
+    frame: u64,
+    chunk: usize,
+    offset: usize,
+    len: usize,
+    _pd: PhantomData<fn(&T)>,
+}
+
+impl<T> FrameSlice<T> {
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+impl<T> Copy for FrameSlice<T> {}
+
+impl<T> Clone for FrameSlice<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+/// Two frame arenas, so that data of the previous frame stays readable for one more frame.
+pub struct FrameArenas {
+    current: FrameArena,
+    previous: FrameArena,
+}
+
+impl FrameArenas {
+    pub fn new() -> Self {
+        Self {
+            current: FrameArena::new(),
+            previous: FrameArena::new(),
+        }
+    }
+
+    pub fn current(&self) -> &FrameArena {
+        &self.current
+    }
+
+    pub fn previous(&self) -> &FrameArena {
+        &self.previous
+    }
+
+    /// Resets the arena of the previous frame, which becomes the arena of the next frame.
+    pub fn end_frame(&mut self) {
+        self.previous.reset();
+        std::mem::swap(&mut self.current, &mut self.previous);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn allocations_grow_and_merge_chunks() {
+        let mut arena = FrameArena::new();
+
+        let value = arena.alloc(7u8);
+        *value += 1;
+        assert_eq!(*value, 8);
+
+        let aligned = arena.alloc(1u64);
+        assert_eq!((aligned as *const u64).align_offset(align_of::<u64>()), 0);
+        assert_eq!(arena.alloc_str("text"), "text");
+
+        let large = arena.alloc_slice_fill_with(MIN_CHUNK_CAPACITY, |index| index as u8);
+        assert_eq!(
+            large[MIN_CHUNK_CAPACITY - 1],
+            (MIN_CHUNK_CAPACITY - 1) as u8
+        );
+
+        let stats = arena.stats();
+        assert_eq!(stats.allocations, 4);
+        assert_eq!(stats.capacity, 3 * MIN_CHUNK_CAPACITY);
+        assert!(stats.used >= MIN_CHUNK_CAPACITY + 13);
+
+        arena.reset();
+        let stats = arena.stats();
+        assert_eq!(stats.used, 0);
+        assert_eq!(stats.allocations, 0);
+        assert_eq!(stats.capacity, 3 * MIN_CHUNK_CAPACITY);
+        assert!(stats.peak >= MIN_CHUNK_CAPACITY);
+        assert_eq!(arena.chunks.borrow().len(), 1);
+    }
+
+    #[test]
+    fn slices_stay_readable_for_one_more_frame() {
+        let mut arenas = FrameArenas::new();
+
+        let slice = arenas.current().push_slice(&[1, 2, 3]);
+        let empty = arenas.current().push_slice::<u32>(&[]);
+        assert_eq!(arenas.current().slice(slice), Some(&[1, 2, 3][..]));
+        assert_eq!(arenas.current().slice(empty), Some(&[][..]));
+
+        arenas.end_frame();
+        assert_eq!(arenas.previous().slice(slice), Some(&[1, 2, 3][..]));
+        assert_eq!(arenas.current().slice(slice), None);
+
+        arenas.current().push_slice(&[4, 5, 6]);
+        arenas.end_frame();
+        assert_eq!(arenas.previous().slice(slice), None);
+        assert_eq!(arenas.current().slice(slice), None);
+    }
+}
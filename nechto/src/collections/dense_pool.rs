@@ -0,0 +1,363 @@
+use crate::collections::pool::current_handle;
+use crate::handle::{Handle, HandleAllocator, LoadIds, SaveIds, StableId, StableIdError};
+
+/// A pool storing its values packed in one array, which makes iterating many values faster at
+/// the cost of moving the last value into the place of a removed one.
+pub struct DensePool<T> {
+    allocator: HandleAllocator<T>,
+    /// Index of the value of each handle index in `values`.
+    value_indexes: Vec<Option<u32>>,
+    values: Vec<T>,
+    /// Handle index of each value.
+    value_handles: Vec<u32>,
+}
+
+impl<T> DensePool<T> {
+    pub fn new() -> Self {
+        Self {
+            allocator: HandleAllocator::new(),
+            value_indexes: Vec::new(),
+            values: Vec::new(),
+            value_handles: Vec::new(),
+        }
+    }
+
+    pub fn insert(&mut self, value: T) -> Handle<T> {
+        let handle = self.allocator.allocate_handle();
+        self.flush();
+        self.push_value(handle, value);
+        handle
+    }
+
+    /// Reserves a handle from any thread, the value is inserted later with `insert_reserved`.
+    pub fn reserve(&self) -> Handle<T> {
+        self.allocator.reserve_handle()
+    }
+
+    /// Inserts the value of a reserved handle, or returns it if the handle was removed or already
+    /// has a value.
+    pub fn insert_reserved(&mut self, handle: Handle<T>, value: T) -> Result<(), T> {
+        self.flush();
+
+        if !self.allocator.is_current(handle) {
+            return Err(value);
+        }
+
+        if self.value_index(handle).is_some() {
+            return Err(value);
+        }
+
+        self.push_value(handle, value);
+        Ok(())
+    }
+
+    fn push_value(&mut self, handle: Handle<T>, value: T) {
+        let value_index = u32::try_from(self.values.len()).expect("pool is full");
+
+        self.values.push(value);
+        self.value_handles.push(handle.index());
+        self.value_indexes[handle.to_usize()] = Some(value_index);
+    }
+
+    fn flush(&mut self) {
+        self.allocator.flush();
+        self.value_indexes.resize(self.allocator.len(), None);
+    }
+
+    /// Removes and returns the value, or `None` if the handle is stale or was already removed.
+    /// Removing a reserved handle without a value cancels the reservation.
+    pub fn remove(&mut self, handle: Handle<T>) -> Option<T> {
+        self.flush();
+
+        if !self.allocator.free(handle) {
+            return None;
+        }
+
+        let value_index = self.value_indexes[handle.to_usize()].take()? as usize;
+
+        let value = self.values.swap_remove(value_index);
+        self.value_handles.swap_remove(value_index);
+
+        if let Some(moved) = self.value_handles.get(value_index) {
+            self.value_indexes[*moved as usize] = Some(value_index as u32);
+        }
+
+        Some(value)
+    }
+
+    fn value_index(&self, handle: Handle<T>) -> Option<usize> {
+        if !self.allocator.is_current(handle) {
+            return None;
+        }
+
+        let index = (*self.value_indexes.get(handle.to_usize())?)?;
+        Some(index as usize)
+    }
+
+    fn handle_at(&self, value_index: usize) -> Handle<T> {
+        current_handle(&self.allocator, self.value_handles[value_index] as usize)
+    }
+
+    pub fn contains(&self, handle: Handle<T>) -> bool {
+        self.value_index(handle).is_some()
+    }
+
+    pub fn get(&self, handle: Handle<T>) -> Option<&T> {
+        self.value_index(handle).map(|index| &self.values[index])
+    }
+
+    pub fn get_mut(&mut self, handle: Handle<T>) -> Option<&mut T> {
+        self.value_index(handle)
+            .map(|index| &mut self.values[index])
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// All values in storage order, which changes when values are removed.
+    pub fn values(&self) -> &[T] {
+        &self.values
+    }
+
+    pub fn values_mut(&mut self) -> &mut [T] {
+        &mut self.values
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Handle<T>, &T)> {
+        self.values
+            .iter()
+            .enumerate()
+            .map(|(index, value)| (self.handle_at(index), value))
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (Handle<T>, &mut T)> {
+        let allocator = &self.allocator;
+
+        self.values
+            .iter_mut()
+            .zip(&self.value_handles)
+            .map(move |(value, index)| (current_handle(allocator, *index as usize), value))
+    }
+
+    pub fn handles(&self) -> impl Iterator<Item = Handle<T>> {
+        (0..self.values.len()).map(|index| self.handle_at(index))
+    }
+
+    /// Removes the values for which `keep` returns false.
+    pub fn retain(&mut self, mut keep: impl FnMut(Handle<T>, &mut T) -> bool) {
+        let mut index = 0;
+
+        // Removing moves the last value into the current index, which is then visited again.
+        while index < self.values.len() {
+            let handle = self.handle_at(index);
+
+            if keep(handle, &mut self.values[index]) {
+                index += 1;
+            } else {
+                self.remove(handle);
+            }
+        }
+    }
+
+    /// Removes and returns all values, their handles become stale.
+    pub fn drain(&mut self) -> std::vec::IntoIter<(Handle<T>, T)> {
+        let handles: Vec<Handle<T>> = self.handles().collect();
+
+        handles
+            .into_iter()
+            .filter_map(|handle| Some((handle, self.remove(handle)?)))
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    /// Removes all values, their handles become stale.
+    pub fn clear(&mut self) {
+        self.retain(|_, _| false);
+    }
+
+    /// Returns the values converted by `write` with their stable IDs, see `Pool::save`.
+    pub fn save<S, E>(
+        &self,
+        mut write: impl FnMut(&T, &SaveIds<T>) -> Result<S, E>,
+    ) -> Result<Vec<(StableId, S)>, E> {
+        let ids = SaveIds::new(self.handles());
+
+        self.iter()
+            .map(|(handle, value)| {
+                let id = ids.id(handle).expect("handle of the pool");
+                Ok((id, write(value, &ids)?))
+            })
+            .collect()
+    }
+
+    /// Inserts saved values converted by `read` and returns their handles, see `Pool::load`.
+    /// Nothing is inserted on errors.
+    pub fn load<S, E: From<StableIdError>>(
+        &mut self,
+        saved: Vec<(StableId, S)>,
+        mut read: impl FnMut(S, &LoadIds<T>) -> Result<T, E>,
+    ) -> Result<LoadIds<T>, E> {
+        let mut ids = LoadIds::new();
+        let mut reserved = Vec::new();
+
+        for (id, _) in &saved {
+            let handle = self.reserve();
+            reserved.push(handle);
+
+            if let Err(err) = ids.insert(*id, handle) {
+                self.cancel(&reserved);
+                return Err(err.into());
+            }
+        }
+
+        let mut values = Vec::new();
+
+        for (_, saved) in saved {
+            match read(saved, &ids) {
+                Ok(value) => values.push(value),
+                Err(err) => {
+                    self.cancel(&reserved);
+                    return Err(err);
+                }
+            }
+        }
+
+        for (handle, value) in reserved.into_iter().zip(values) {
+            // The handles were reserved above and `read` can't reach the pool, so they are still
+            // current and have no values.
+            assert!(
+                self.insert_reserved(handle, value).is_ok(),
+                "reserved handle of a loaded value"
+            );
+        }
+
+        Ok(ids)
+    }
+
+    fn cancel(&mut self, reserved: &[Handle<T>]) {
+        for handle in reserved {
+            self.remove(*handle);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn insert_reserved_keeps_existing_values() {
+        let mut pool = DensePool::new();
+
+        let handle = pool.reserve();
+        assert_eq!(pool.insert_reserved(handle, 1), Ok(()));
+        assert_eq!(pool.insert_reserved(handle, 2), Err(2));
+        assert_eq!(pool.get(handle), Some(&1));
+        assert_eq!(pool.len(), 1);
+
+        let inserted = pool.insert(3);
+        assert_eq!(pool.insert_reserved(inserted, 4), Err(4));
+
+        let removed = pool.reserve();
+        pool.remove(removed);
+        assert_eq!(pool.insert_reserved(removed, 5), Err(5));
+        assert_eq!(pool.len(), 2);
+    }
+
+    #[test]
+    fn removing_moves_the_last_value() {
+        let mut pool = DensePool::new();
+        let handles: Vec<_> = (0..5).map(|value| pool.insert(value)).collect();
+
+        assert_eq!(pool.remove(handles[1]), Some(1));
+        assert_eq!(pool.values(), [0, 4, 2, 3]);
+        assert_eq!(pool.get(handles[4]), Some(&4));
+        assert_eq!(pool.get(handles[1]), None);
+
+        for value in pool.values_mut() {
+            *value += 10;
+        }
+
+        pool.retain(|_, value| *value % 2 == 0);
+        assert_eq!(pool.values(), [10, 14, 12]);
+        assert_eq!(
+            pool.iter().collect::<Vec<_>>(),
+            [(handles[0], &10), (handles[4], &14), (handles[2], &12)]
+        );
+        assert!(!pool.contains(handles[3]));
+
+        let drained: Vec<_> = pool.drain().collect();
+        assert_eq!(drained.len(), 3);
+        assert!(pool.is_empty());
+        assert!(drained.iter().all(|(handle, _)| !pool.contains(*handle)));
+    }
+
+    /// Values referring to other values of the same pool.
+    struct Node {
+        name: &'static str,
+        next: Handle<Node>,
+    }
+
+    #[test]
+    fn saved_values_load_in_storage_order() {
+        let mut pool = DensePool::new();
+        let a = pool.insert(Node {
+            name: "a",
+            next: Handle::NIL,
+        });
+        let b = pool.insert(Node { name: "b", next: a });
+        pool.insert(Node { name: "c", next: b });
+
+        let save = |pool: &DensePool<Node>| {
+            pool.save(|node, ids| Ok::<_, StableIdError>((node.name, ids.id(node.next)?)))
+        };
+
+        // Saving a handle to a removed value fails instead of dropping the reference.
+        pool.remove(a);
+        assert!(matches!(save(&pool), Err(StableIdError::DanglingHandle(_))));
+
+        pool.get_mut(b).unwrap().next = Handle::NIL;
+        let saved = save(&pool).unwrap();
+
+        let mut loaded = DensePool::new();
+        loaded
+            .load(saved, |(name, next), ids| {
+                Ok::<_, StableIdError>(Node {
+                    name,
+                    next: ids.handle(next)?,
+                })
+            })
+            .unwrap();
+
+        let names = |pool: &DensePool<Node>| -> Vec<&str> {
+            pool.values().iter().map(|node| node.name).collect()
+        };
+        assert_eq!(names(&loaded), names(&pool));
+
+        let c = loaded
+            .values()
+            .iter()
+            .find(|node| node.name == "c")
+            .unwrap();
+        assert_eq!(loaded.get(c.next).unwrap().name, "b");
+    }
+
+    #[test]
+    fn failed_loads_insert_nothing() {
+        let mut pool: DensePool<&str> = DensePool::new();
+
+        let result = pool.load(vec![(StableId(1), 2)], |other, ids| {
+            ids.handle(StableId(other)).map(|_| "a")
+        });
+        assert!(matches!(
+            result,
+            Err(StableIdError::DanglingId(StableId(2)))
+        ));
+        assert!(pool.is_empty());
+    }
+}
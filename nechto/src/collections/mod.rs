@@ -1,3 +1,7 @@
+mod arena;
+mod dense_pool;
 mod pool;
 
+pub use self::arena::{ArenaStats, FrameArena, FrameArenas, FrameSlice};
+pub use self::dense_pool::DensePool;
 pub use self::pool::Pool;
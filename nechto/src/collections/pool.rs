@@ -1,41 +1,408 @@
-use crate::handle::Handle;
+use crate::handle::{Handle, HandleAllocator, LoadIds, SaveIds, StableId, StableIdError};
 
+/// Values stored in place, iteration skips the slots of removed values. See `DensePool` for
+/// packed storage.
 pub struct Pool<T> {
-    items: Vec<Option<T>>,
-    free_indexes: Vec<usize>,
+    allocator: HandleAllocator<T>,
+    values: Vec<Option<T>>,
+    len: usize,
 }
 
 impl<T> Pool<T> {
     pub fn new() -> Self {
         Self {
-            items: Vec::new(),
-            free_indexes: Vec::new(),
+            allocator: HandleAllocator::new(),
+            values: Vec::new(),
+            len: 0,
         }
     }
 
     pub fn insert(&mut self, value: T) -> Handle<T> {
-        if let Some(index) = self.free_indexes.pop() {
-            self.items[index] = Some(value);
-            Handle::from_usize(index)
-        } else {
-            let index = self.items.len();
-            self.items.push(Some(value));
-            Handle::from_usize(index)
+        let handle = self.allocator.allocate_handle();
+        self.flush();
+
+        self.values[handle.to_usize()] = Some(value);
+        self.len += 1;
+        handle
+    }
+
+    /// Reserves a handle from any thread, the value is inserted later with `insert_reserved`.
+    pub fn reserve(&self) -> Handle<T> {
+        self.allocator.reserve_handle()
+    }
+
+    /// Inserts the value of a reserved handle, or returns it if the handle was removed or already
+    /// has a value.
+    pub fn insert_reserved(&mut self, handle: Handle<T>, value: T) -> Result<(), T> {
+        self.flush();
+
+        if !self.allocator.is_current(handle) {
+            return Err(value);
         }
+
+        let slot = &mut self.values[handle.to_usize()];
+
+        if slot.is_some() {
+            return Err(value);
+        }
+
+        *slot = Some(value);
+        self.len += 1;
+        Ok(())
     }
 
-    pub fn remove(&mut self, handle: Handle<T>) {
-        self.free_indexes.push(handle.to_usize());
-        self.items[handle.to_usize()] = None;
+    fn flush(&mut self) {
+        self.allocator.flush();
+        self.values.resize_with(self.allocator.len(), || None);
+    }
+
+    /// Removes and returns the value, or `None` if the handle is stale or was already removed.
+    /// Removing a reserved handle without a value cancels the reservation.
+    pub fn remove(&mut self, handle: Handle<T>) -> Option<T> {
+        self.flush();
+
+        if !self.allocator.free(handle) {
+            return None;
+        }
+
+        let value = self.values[handle.to_usize()].take();
+
+        if value.is_some() {
+            self.len -= 1;
+        }
+
+        value
+    }
+
+    pub fn contains(&self, handle: Handle<T>) -> bool {
+        self.get(handle).is_some()
     }
 
     pub fn get(&self, handle: Handle<T>) -> Option<&T> {
-        self.items.get(handle.to_usize()).and_then(|x| x.as_ref())
+        if !self.allocator.is_current(handle) {
+            return None;
+        }
+
+        self.values.get(handle.to_usize())?.as_ref()
     }
 
     pub fn get_mut(&mut self, handle: Handle<T>) -> Option<&mut T> {
-        self.items
-            .get_mut(handle.to_usize())
-            .and_then(|x| x.as_mut())
+        if !self.allocator.is_current(handle) {
+            return None;
+        }
+
+        self.values.get_mut(handle.to_usize())?.as_mut()
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Handle<T>, &T)> {
+        let allocator = &self.allocator;
+
+        self.values
+            .iter()
+            .enumerate()
+            .filter_map(move |(index, value)| {
+                Some((current_handle(allocator, index), value.as_ref()?))
+            })
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (Handle<T>, &mut T)> {
+        let allocator = &self.allocator;
+
+        self.values
+            .iter_mut()
+            .enumerate()
+            .filter_map(move |(index, value)| {
+                Some((current_handle(allocator, index), value.as_mut()?))
+            })
+    }
+
+    pub fn handles(&self) -> impl Iterator<Item = Handle<T>> {
+        self.iter().map(|(handle, _)| handle)
+    }
+
+    /// Removes the values for which `keep` returns false.
+    pub fn retain(&mut self, mut keep: impl FnMut(Handle<T>, &mut T) -> bool) {
+        let removed: Vec<Handle<T>> = self
+            .iter_mut()
+            .filter_map(|(handle, value)| (!keep(handle, value)).then_some(handle))
+            .collect();
+
+        for handle in removed {
+            self.remove(handle);
+        }
+    }
+
+    /// Removes and returns all values, their handles become stale.
+    pub fn drain(&mut self) -> std::vec::IntoIter<(Handle<T>, T)> {
+        let handles: Vec<Handle<T>> = self.handles().collect();
+
+        handles
+            .into_iter()
+            .filter_map(|handle| Some((handle, self.remove(handle)?)))
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    /// Removes all values, their handles become stale.
+    pub fn clear(&mut self) {
+        self.retain(|_, _| false);
+    }
+
+    /// Returns the values converted by `write` with their stable IDs. `write` can replace handles
+    /// between values of the pool by stable IDs.
+    pub fn save<S, E>(
+        &self,
+        mut write: impl FnMut(&T, &SaveIds<T>) -> Result<S, E>,
+    ) -> Result<Vec<(StableId, S)>, E> {
+        let ids = SaveIds::new(self.handles());
+
+        self.iter()
+            .map(|(handle, value)| {
+                let id = ids.id(handle).expect("handle of the pool");
+                Ok((id, write(value, &ids)?))
+            })
+            .collect()
+    }
+
+    /// Inserts saved values converted by `read` and returns their handles. `read` can replace
+    /// stable IDs by handles of any of the loaded values. Nothing is inserted on errors.
+    pub fn load<S, E: From<StableIdError>>(
+        &mut self,
+        saved: Vec<(StableId, S)>,
+        mut read: impl FnMut(S, &LoadIds<T>) -> Result<T, E>,
+    ) -> Result<LoadIds<T>, E> {
+        let mut ids = LoadIds::new();
+        let mut reserved = Vec::new();
+
+        // All values get handles first, so that values can refer to the ones loaded after them.
+        for (id, _) in &saved {
+            let handle = self.reserve();
+            reserved.push(handle);
+
+            if let Err(err) = ids.insert(*id, handle) {
+                self.cancel(&reserved);
+                return Err(err.into());
+            }
+        }
+
+        let mut values = Vec::new();
+
+        for (_, saved) in saved {
+            match read(saved, &ids) {
+                Ok(value) => values.push(value),
+                Err(err) => {
+                    self.cancel(&reserved);
+                    return Err(err);
+                }
+            }
+        }
+
+        for (handle, value) in reserved.into_iter().zip(values) {
+            // The handles were reserved above and `read` can't reach the pool, so the slots are
+            // still current and empty.
+            assert!(
+                self.insert_reserved(handle, value).is_ok(),
+                "reserved handle of a loaded value"
+            );
+        }
+
+        Ok(ids)
+    }
+
+    fn cancel(&mut self, reserved: &[Handle<T>]) {
+        for handle in reserved {
+            self.remove(*handle);
+        }
+    }
+}
+
+/// The handle of an allocated index.
+pub(crate) fn current_handle<T>(allocator: &HandleAllocator<T>, index: usize) -> Handle<T> {
+    let index = index as u32;
+    Handle::new(index, allocator.generation(index).unwrap_or(0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn insert_reserved_keeps_existing_values() {
+        let mut pool = Pool::new();
+
+        let handle = pool.reserve();
+        assert_eq!(pool.insert_reserved(handle, 1), Ok(()));
+        assert_eq!(pool.insert_reserved(handle, 2), Err(2));
+        assert_eq!(pool.get(handle), Some(&1));
+        assert_eq!(pool.len(), 1);
+
+        let inserted = pool.insert(3);
+        assert_eq!(pool.insert_reserved(inserted, 4), Err(4));
+
+        let removed = pool.reserve();
+        pool.remove(removed);
+        assert_eq!(pool.insert_reserved(removed, 5), Err(5));
+        assert_eq!(pool.len(), 2);
+    }
+
+    #[test]
+    fn stale_handles_do_not_resolve() {
+        let mut pool = Pool::new();
+
+        let first = pool.insert("first");
+        assert_eq!(pool.remove(first), Some("first"));
+        assert_eq!(pool.remove(first), None);
+
+        // The index is reused with the next generation.
+        let second = pool.insert("second");
+        assert_eq!(second.index(), first.index());
+        assert_ne!(second, first);
+
+        assert_eq!(pool.get(first), None);
+        assert_eq!(pool.get_mut(first), None);
+        assert!(!pool.contains(first));
+        assert_eq!(pool.remove(first), None);
+        assert_eq!(pool.get(second), Some(&"second"));
+
+        assert_eq!(pool.get(Handle::NIL), None);
+        assert_eq!(pool.remove(Handle::NIL), None);
+        assert_eq!(pool.len(), 1);
+    }
+
+    #[test]
+    fn bulk_operations_skip_removed_values() {
+        let mut pool = Pool::new();
+        let handles: Vec<_> = (0..6).map(|value| pool.insert(value)).collect();
+        pool.remove(handles[1]);
+
+        for (_, value) in pool.iter_mut() {
+            *value *= 10;
+        }
+
+        assert_eq!(
+            pool.iter().collect::<Vec<_>>(),
+            [
+                (handles[0], &0),
+                (handles[2], &20),
+                (handles[3], &30),
+                (handles[4], &40),
+                (handles[5], &50),
+            ]
+        );
+
+        pool.retain(|handle, value| handle != handles[0] && *value != 30);
+        assert_eq!(
+            pool.handles().collect::<Vec<_>>(),
+            [handles[2], handles[4], handles[5]]
+        );
+        assert!(!pool.contains(handles[3]));
+
+        let drained: Vec<_> = pool.drain().collect();
+        assert_eq!(
+            drained,
+            [(handles[2], 20), (handles[4], 40), (handles[5], 50)]
+        );
+        assert!(pool.is_empty());
+        assert!(
+            drained
+                .iter()
+                .all(|(handle, _)| pool.get(*handle).is_none())
+        );
+
+        let handle = pool.insert(7);
+        pool.clear();
+        assert!(!pool.contains(handle));
+        assert_eq!(pool.iter().count(), 0);
+    }
+
+    /// Values referring to other values of the same pool.
+    struct Node {
+        name: &'static str,
+        next: Handle<Node>,
+    }
+
+    #[test]
+    fn loaded_values_refer_to_each_other() {
+        let mut pool = Pool::new();
+        let last = pool.insert(Node {
+            name: "last",
+            next: Handle::NIL,
+        });
+        let first = pool.insert(Node {
+            name: "first",
+            next: last,
+        });
+        let removed = pool.insert(Node {
+            name: "removed",
+            next: first,
+        });
+        pool.remove(removed);
+
+        let saved = pool
+            .save(|node, ids| Ok::<_, StableIdError>((node.name, ids.id(node.next)?)))
+            .unwrap();
+        assert_eq!(saved.len(), 2);
+
+        let mut loaded = Pool::new();
+        loaded.insert(Node {
+            name: "existing",
+            next: Handle::NIL,
+        });
+
+        let ids = loaded
+            .load(saved.clone(), |(name, next), ids| {
+                Ok::<_, StableIdError>(Node {
+                    name,
+                    next: ids.handle(next)?,
+                })
+            })
+            .unwrap();
+
+        let first = ids.handle(saved[1].0).unwrap();
+        let last = loaded.get(first).unwrap().next;
+        assert_eq!(loaded.get(first).unwrap().name, "first");
+        assert_eq!(loaded.get(last).unwrap().name, "last");
+        assert!(loaded.get(last).unwrap().next.is_nil());
+        assert_eq!(loaded.len(), 3);
+    }
+
+    #[test]
+    fn failed_loads_insert_nothing() {
+        let mut pool: Pool<Node> = Pool::new();
+
+        let duplicate = vec![(StableId(1), "a"), (StableId(1), "b")];
+        let result = pool.load(duplicate, |name, _| {
+            Ok::<_, StableIdError>(Node {
+                name,
+                next: Handle::NIL,
+            })
+        });
+        assert!(matches!(
+            result,
+            Err(StableIdError::DuplicateId(StableId(1)))
+        ));
+
+        let dangling = vec![(StableId(1), StableId(2))];
+        let result = pool.load(dangling, |next, ids| {
+            Ok::<_, StableIdError>(Node {
+                name: "a",
+                next: ids.handle(next)?,
+            })
+        });
+        assert!(matches!(
+            result,
+            Err(StableIdError::DanglingId(StableId(2)))
+        ));
+
+        assert!(pool.is_empty());
+        assert_eq!(pool.handles().count(), 0);
     }
 }
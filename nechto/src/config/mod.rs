@@ -7,6 +7,8 @@ pub struct Config {
     pub window_width: u32,
     pub window_height: u32,
     pub render: RenderConfig,
+    pub script: ScriptConfig,
+    pub input: InputConfig,
 }
 
 #[derive(Clone)]
@@ -14,6 +16,21 @@ pub struct RenderConfig {
     pub vulkan_enable_debug: bool,
 }
 
+#[derive(Clone)]
+pub struct ScriptConfig {
+    pub profile: bool,
+    /// Milliseconds between stack samples taken while profiling, 0 disables sampling.
+    pub profile_sample_interval: u32,
+}
+
+#[derive(Clone)]
+pub struct InputConfig {
+    /// VFS path the input of the session is recorded to on exit, empty disables recording.
+    pub record: String,
+    /// VFS path of a recording replayed on start, empty disables replaying.
+    pub replay: String,
+}
+
 impl Config {
     pub fn parse(text: &str) -> Self {
         let map = ValueMap::parse_ini(text);
@@ -24,6 +41,14 @@ impl Config {
             render: RenderConfig {
                 vulkan_enable_debug: map.bool("vulkan.enable_debug", false),
             },
+            script: ScriptConfig {
+                profile: map.bool("script.profile", false),
+                profile_sample_interval: map.u32("script.profile_sample_interval", 0),
+            },
+            input: InputConfig {
+                record: map.string("input.record", String::new()),
+                replay: map.string("input.replay", String::new()),
+            },
         }
     }
 
@@ -0,0 +1,307 @@
+use std::collections::BTreeMap;
+
+/// Dynamically typed event data that can cross the boundary between Rust and scripts.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum Payload {
+    #[default]
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Payload>),
+    Object(BTreeMap<String, Payload>),
+}
+
+impl Payload {
+    pub fn object<K: Into<String>>(fields: impl IntoIterator<Item = (K, Payload)>) -> Self {
+        Payload::Object(
+            fields
+                .into_iter()
+                .map(|(key, value)| (key.into(), value))
+                .collect(),
+        )
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Payload> {
+        match self {
+            Payload::Object(fields) => fields.get(key),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Payload::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Payload::Number(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Payload::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Payload]> {
+        match self {
+            Payload::Array(values) => Some(values),
+            _ => None,
+        }
+    }
+}
+
+impl From<bool> for Payload {
+    fn from(value: bool) -> Self {
+        Payload::Bool(value)
+    }
+}
+
+impl From<f64> for Payload {
+    fn from(value: f64) -> Self {
+        Payload::Number(value)
+    }
+}
+
+impl From<f32> for Payload {
+    fn from(value: f32) -> Self {
+        Payload::Number(value as f64)
+    }
+}
+
+impl From<i32> for Payload {
+    fn from(value: i32) -> Self {
+        Payload::Number(value as f64)
+    }
+}
+
+impl From<u32> for Payload {
+    fn from(value: u32) -> Self {
+        Payload::Number(value as f64)
+    }
+}
+
+impl From<String> for Payload {
+    fn from(value: String) -> Self {
+        Payload::String(value)
+    }
+}
+
+impl From<&str> for Payload {
+    fn from(value: &str) -> Self {
+        Payload::String(value.to_owned())
+    }
+}
+
+impl<T: Into<Payload>> From<Vec<T>> for Payload {
+    fn from(values: Vec<T>) -> Self {
+        Payload::Array(values.into_iter().map(Into::into).collect())
+    }
+}
+
+impl<T: Into<Payload>> From<Option<T>> for Payload {
+    fn from(value: Option<T>) -> Self {
+        value.map(Into::into).unwrap_or(Payload::Null)
+    }
+}
+
+/// An event with a well-known name and a payload layout shared by Rust and scripts.
+pub trait Event: Sized + 'static {
+    const NAME: &'static str;
+
+    fn to_payload(&self) -> Payload;
+
+    fn from_payload(payload: &Payload) -> Option<Self>;
+}
+
+#[derive(Debug, Clone)]
+pub struct RawEvent {
+    pub name: String,
+    pub payload: Payload,
+}
+
+/// Queue of named events exchanged between Rust systems and scripts.
+///
+/// Events emitted during a frame become readable (and are delivered to script listeners) on the
+/// next `EventHandler::on_update`, and stay readable until the one after it.
+pub struct EventBus {
+    pending: Vec<RawEvent>,
+    current: Vec<RawEvent>,
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        Self {
+            pending: Vec::new(),
+            current: Vec::new(),
+        }
+    }
+
+    pub fn emit<E: Event>(&mut self, event: E) {
+        self.emit_raw(E::NAME, event.to_payload());
+    }
+
+    pub fn emit_raw(&mut self, name: impl Into<String>, payload: Payload) {
+        self.pending.push(RawEvent {
+            name: name.into(),
+            payload,
+        });
+    }
+
+    pub fn read<E: Event>(&self) -> impl Iterator<Item = E> + '_ {
+        self.read_raw(E::NAME).filter_map(E::from_payload)
+    }
+
+    pub fn read_raw<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Payload> + 'a {
+        self.current
+            .iter()
+            .filter(move |event| event.name == name)
+            .map(|event| &event.payload)
+    }
+
+    pub fn events(&self) -> impl Iterator<Item = &RawEvent> + Clone {
+        self.current.iter()
+    }
+
+    /// Makes events emitted since the previous call readable and drops the ones read before.
+    pub fn swap(&mut self) {
+        self.current.clear();
+        std::mem::swap(&mut self.current, &mut self.pending);
+    }
+}
+
+pub struct WindowResized {
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Event for WindowResized {
+    const NAME: &'static str = "window.resized";
+
+    fn to_payload(&self) -> Payload {
+        Payload::object([("width", self.width.into()), ("height", self.height.into())])
+    }
+
+    fn from_payload(payload: &Payload) -> Option<Self> {
+        Some(Self {
+            width: payload.get("width")?.as_f64()? as u32,
+            height: payload.get("height")?.as_f64()? as u32,
+        })
+    }
+}
+
+pub struct ActionPressed {
+    pub action: String,
+}
+
+impl Event for ActionPressed {
+    const NAME: &'static str = "action.pressed";
+
+    fn to_payload(&self) -> Payload {
+        Payload::object([("action", self.action.as_str().into())])
+    }
+
+    fn from_payload(payload: &Payload) -> Option<Self> {
+        Some(Self {
+            action: payload.get("action")?.as_str()?.to_owned(),
+        })
+    }
+}
+
+pub struct ActionReleased {
+    pub action: String,
+}
+
+impl Event for ActionReleased {
+    const NAME: &'static str = "action.released";
+
+    fn to_payload(&self) -> Payload {
+        Payload::object([("action", self.action.as_str().into())])
+    }
+
+    fn from_payload(payload: &Payload) -> Option<Self> {
+        Some(Self {
+            action: payload.get("action")?.as_str()?.to_owned(),
+        })
+    }
+}
+
+/// The interaction of an action was performed, see `input::Interaction`.
+pub struct ActionPerformed {
+    pub action: String,
+}
+
+impl Event for ActionPerformed {
+    const NAME: &'static str = "action.performed";
+
+    fn to_payload(&self) -> Payload {
+        Payload::object([("action", self.action.as_str().into())])
+    }
+
+    fn from_payload(payload: &Payload) -> Option<Self> {
+        Some(Self {
+            action: payload.get("action")?.as_str()?.to_owned(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn events_are_readable_for_one_frame_after_emitting() {
+        let mut bus = EventBus::new();
+
+        bus.emit(WindowResized {
+            width: 640,
+            height: 480,
+        });
+        bus.emit_raw("custom", Payload::from(vec![1, 2]));
+        assert_eq!(bus.events().count(), 0);
+
+        bus.swap();
+        bus.emit(ActionPressed {
+            action: "jump".to_string(),
+        });
+
+        let resized: Vec<WindowResized> = bus.read().collect();
+        assert_eq!(resized.len(), 1);
+        assert_eq!((resized[0].width, resized[0].height), (640, 480));
+        assert_eq!(
+            bus.read_raw("custom").collect::<Vec<_>>(),
+            [&Payload::from(vec![1, 2])]
+        );
+        assert_eq!(bus.read::<ActionPressed>().count(), 0);
+
+        bus.swap();
+        assert_eq!(bus.read::<WindowResized>().count(), 0);
+        assert_eq!(
+            bus.read::<ActionPressed>()
+                .map(|event| event.action)
+                .collect::<Vec<_>>(),
+            ["jump"]
+        );
+
+        bus.swap();
+        assert_eq!(bus.events().count(), 0);
+    }
+
+    #[test]
+    fn mismatched_payloads_are_skipped() {
+        let mut bus = EventBus::new();
+        bus.emit_raw(ActionPressed::NAME, Payload::object([("action", 1.into())]));
+        bus.emit_raw(ActionPressed::NAME, Payload::Null);
+        bus.swap();
+
+        assert_eq!(bus.read_raw(ActionPressed::NAME).count(), 2);
+        assert_eq!(bus.read::<ActionPressed>().count(), 0);
+    }
+}
@@ -1,22 +1,185 @@
 use std::marker::PhantomData;
+use std::sync::atomic::{AtomicI64, Ordering};
 
 use crate::handle::Handle;
 
+/// Allocates generational handles and recycles the indexes of freed ones.
+///
+/// Handles can be reserved through a shared reference, for example from loading threads, without
+/// locking. Reservations become allocated handles on the next `flush`, which every method taking
+/// `&mut self` does first.
 pub struct HandleAllocator<T> {
-    last_index: u64,
+    /// Current generation of each index, indexes are allocated unless they are in `free_indexes`.
+    generations: Vec<u32>,
+    free_indexes: Vec<u32>,
+    /// Reservations take free indexes from the end of `free_indexes` and then new indexes past
+    /// `generations`, counting this cursor down from the number of free indexes.
+    free_cursor: AtomicI64,
     _pd: PhantomData<fn(&T)>,
 }
 
 impl<T> HandleAllocator<T> {
     pub fn new() -> Self {
         Self {
-            last_index: 0,
+            generations: Vec::new(),
+            free_indexes: Vec::new(),
+            free_cursor: AtomicI64::new(0),
             _pd: PhantomData,
         }
     }
 
     pub fn allocate_handle(&mut self) -> Handle<T> {
-        self.last_index += 1;
-        Handle::new(self.last_index)
+        self.flush();
+
+        let handle = match self.free_indexes.pop() {
+            Some(index) => Handle::new(index, self.generations[index as usize]),
+            None => {
+                let index = u32::try_from(self.generations.len()).expect("out of handles");
+                self.generations.push(1);
+                Handle::new(index, 1)
+            }
+        };
+
+        *self.free_cursor.get_mut() = self.free_indexes.len() as i64;
+        handle
+    }
+
+    /// Reserves a handle from any thread, it is allocated on the next `flush`.
+    pub fn reserve_handle(&self) -> Handle<T> {
+        let cursor = self.free_cursor.fetch_sub(1, Ordering::Relaxed);
+
+        if cursor > 0 {
+            let index = self.free_indexes[cursor as usize - 1];
+            Handle::new(index, self.generations[index as usize])
+        } else {
+            let index = self.generations.len() as i64 - cursor;
+            Handle::new(u32::try_from(index).expect("out of handles"), 1)
+        }
+    }
+
+    /// Allocates the reserved handles.
+    pub fn flush(&mut self) {
+        let cursor = *self.free_cursor.get_mut();
+
+        if cursor == self.free_indexes.len() as i64 {
+            return;
+        }
+
+        self.free_indexes.truncate(cursor.max(0) as usize);
+
+        let reserved_new = (-cursor).max(0) as usize;
+        self.generations
+            .resize(self.generations.len() + reserved_new, 1);
+
+        *self.free_cursor.get_mut() = self.free_indexes.len() as i64;
+    }
+
+    /// Frees the index of an allocated handle for reuse with the next generation, returns false
+    /// if the handle is stale.
+    pub fn free(&mut self, handle: Handle<T>) -> bool {
+        self.flush();
+
+        if !self.is_current(handle) {
+            return false;
+        }
+
+        let generation = &mut self.generations[handle.to_usize()];
+
+        match generation.checked_add(1) {
+            Some(next) => {
+                *generation = next;
+                self.free_indexes.push(handle.index());
+            }
+            // An index whose generation would wrap around is never reused, old handles could
+            // match it. Its generation is set to 0, which no handle but `NIL` has.
+            None => *generation = 0,
+        }
+
+        *self.free_cursor.get_mut() = self.free_indexes.len() as i64;
+        true
+    }
+
+    /// Whether the handle has the current generation of its index. This is true for allocated
+    /// handles and handles reserved from free indexes, but not for stale handles.
+    pub fn is_current(&self, handle: Handle<T>) -> bool {
+        !handle.is_nil() && self.generation(handle.index()) == Some(handle.generation())
+    }
+
+    pub(crate) fn generation(&self, index: u32) -> Option<u32> {
+        self.generations.get(index as usize).copied()
+    }
+
+    /// Number of indexes handed out so far, including freed ones.
+    pub fn len(&self) -> usize {
+        self.generations.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.generations.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    #[test]
+    fn reservations_from_threads_get_distinct_handles() {
+        let mut allocator: HandleAllocator<()> = HandleAllocator::new();
+        let freed: Vec<_> = (0..4).map(|_| allocator.allocate_handle()).collect();
+
+        for handle in &freed {
+            allocator.free(*handle);
+        }
+
+        let reserved: Vec<Handle<()>> = std::thread::scope(|scope| {
+            let threads: Vec<_> = (0..4)
+                .map(|_| {
+                    scope.spawn(|| {
+                        (0..8)
+                            .map(|_| allocator.reserve_handle())
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect();
+
+            threads
+                .into_iter()
+                .flat_map(|thread| thread.join().unwrap())
+                .collect()
+        });
+
+        let unique: HashSet<_> = reserved.iter().copied().collect();
+        assert_eq!(unique.len(), reserved.len());
+
+        // Freed indexes are reused with their next generation before new indexes are added.
+        let reused = reserved.iter().filter(|handle| handle.index() < 4).count();
+        assert_eq!(reused, 4);
+        assert!(reserved.iter().all(|handle| !freed.contains(handle)));
+
+        allocator.flush();
+        assert_eq!(allocator.len(), 32);
+        assert!(reserved.iter().all(|handle| allocator.is_current(*handle)));
+        assert!(freed.iter().all(|handle| !allocator.is_current(*handle)));
+
+        let next = allocator.allocate_handle();
+        assert_eq!(next.index(), 32);
+    }
+
+    #[test]
+    fn indexes_are_retired_before_their_generation_wraps() {
+        let mut allocator: HandleAllocator<()> = HandleAllocator::new();
+        let handle = allocator.allocate_handle();
+
+        allocator.generations[handle.to_usize()] = u32::MAX;
+        let last = Handle::new(handle.index(), u32::MAX);
+        assert!(allocator.free(last));
+        assert!(!allocator.is_current(last));
+
+        let next = allocator.allocate_handle();
+        assert_ne!(next.index(), handle.index());
+        assert!(!allocator.free(last));
     }
 }
@@ -1,37 +1,75 @@
 mod allocator;
+mod stable;
 
 pub use self::allocator::HandleAllocator;
+pub use self::stable::{Error as StableIdError, LoadIds, SaveIds, StableId};
 
 use std::fmt::Debug;
+use std::hash::{Hash, Hasher};
 use std::marker::PhantomData;
 
-#[derive(Copy, Clone)]
+/// Index of a slot and the generation of the slot when the handle was created, so that handles
+/// to removed values don't resolve to values inserted later into the same slot. Generations of
+/// valid handles start at 1, which keeps them distinct from `NIL`.
 pub struct Handle<T> {
-    value: u64,
+    index: u32,
+    generation: u32,
     _pd: PhantomData<fn(&T)>,
 }
 
 impl<T> Handle<T> {
-    pub const NIL: Self = Self::new(0);
+    pub const NIL: Self = Self::new(0, 0);
 
-    pub const fn new(value: u64) -> Self {
+    pub const fn new(index: u32, generation: u32) -> Self {
         Self {
-            value,
+            index,
+            generation,
             _pd: PhantomData,
         }
     }
 
-    pub const fn from_usize(value: usize) -> Self {
-        Self::new(value as u64)
+    pub const fn index(&self) -> u32 {
+        self.index
+    }
+
+    pub const fn generation(&self) -> u32 {
+        self.generation
     }
 
     pub const fn to_usize(&self) -> usize {
-        self.value as usize
+        self.index as usize
+    }
+
+    pub const fn is_nil(&self) -> bool {
+        self.generation == 0
+    }
+}
+
+impl<T> Copy for Handle<T> {}
+
+impl<T> Clone for Handle<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> PartialEq for Handle<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.index == other.index && self.generation == other.generation
+    }
+}
+
+impl<T> Eq for Handle<T> {}
+
+impl<T> Hash for Handle<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.index.hash(state);
+        self.generation.hash(state);
     }
 }
 
 impl<T> Debug for Handle<T> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "Handle ({})", self.value)
+        write!(f, "Handle ({}v{})", self.index, self.generation)
     }
 }
@@ -0,0 +1,102 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::num::ParseIntError;
+use std::str::FromStr;
+
+use crate::handle::Handle;
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("{0} does not refer to a saved value")]
+    DanglingHandle(String),
+
+    #[error("stable ID {0} does not refer to a loaded value")]
+    DanglingId(StableId),
+
+    #[error("stable ID {0} is used by more than one value")]
+    DuplicateId(StableId),
+}
+
+/// Identifies a saved value independently of the indexes of handles, 0 stands for `NIL`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct StableId(pub u64);
+
+impl StableId {
+    pub const NIL: StableId = StableId(0);
+}
+
+impl fmt::Display for StableId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl FromStr for StableId {
+    type Err = ParseIntError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        text.parse().map(StableId)
+    }
+}
+
+/// Stable IDs of the handles of saved values.
+pub struct SaveIds<T> {
+    ids: HashMap<Handle<T>, StableId>,
+}
+
+impl<T> SaveIds<T> {
+    /// Numbers the handles from 1 in order.
+    pub fn new(handles: impl IntoIterator<Item = Handle<T>>) -> Self {
+        let ids = handles
+            .into_iter()
+            .zip(1..)
+            .map(|(handle, id)| (handle, StableId(id)))
+            .collect();
+
+        Self { ids }
+    }
+
+    pub fn id(&self, handle: Handle<T>) -> Result<StableId, Error> {
+        if handle.is_nil() {
+            return Ok(StableId::NIL);
+        }
+
+        self.ids
+            .get(&handle)
+            .copied()
+            .ok_or_else(|| Error::DanglingHandle(format!("{:?}", handle)))
+    }
+}
+
+/// Handles of loaded values by their stable IDs.
+pub struct LoadIds<T> {
+    handles: HashMap<StableId, Handle<T>>,
+}
+
+impl<T> LoadIds<T> {
+    pub fn new() -> Self {
+        Self {
+            handles: HashMap::new(),
+        }
+    }
+
+    pub fn insert(&mut self, id: StableId, handle: Handle<T>) -> Result<(), Error> {
+        if id == StableId::NIL || self.handles.insert(id, handle).is_some() {
+            return Err(Error::DuplicateId(id));
+        }
+
+        Ok(())
+    }
+
+    pub fn handle(&self, id: StableId) -> Result<Handle<T>, Error> {
+        if id == StableId::NIL {
+            return Ok(Handle::NIL);
+        }
+
+        self.handles.get(&id).copied().ok_or(Error::DanglingId(id))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (StableId, Handle<T>)> {
+        self.handles.iter().map(|(id, handle)| (*id, *handle))
+    }
+}
@@ -0,0 +1,388 @@
+use std::fmt;
+
+use glam::Vec2;
+
+use crate::input::Input;
+use crate::input::binding::parse_gamepad_axis;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GamepadAxis {
+    LeftStickX,
+    LeftStickY,
+    RightStickX,
+    RightStickY,
+    LeftTrigger,
+    RightTrigger,
+}
+
+/// A continuous input value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AnalogAxis {
+    /// Raw mouse motion since the last reset.
+    MouseX,
+    MouseY,
+    /// Scrolled lines since the last reset.
+    WheelX,
+    WheelY,
+    /// Stick axes in `-1..1` with positive `y` up, triggers in `0..1`.
+    Gamepad(GamepadAxis),
+}
+
+/// Where the value of an axis action comes from.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AxisSource {
+    /// `-1` while `negative` is held and `1` while `positive` is held.
+    Buttons {
+        negative: Input,
+        positive: Input,
+    },
+    /// Like `Buttons` but in two dimensions, with positive `y` up.
+    DirectionalButtons {
+        up: Input,
+        down: Input,
+        left: Input,
+        right: Input,
+    },
+    Analog(AnalogAxis),
+    Analog2D {
+        x: AnalogAxis,
+        y: AnalogAxis,
+    },
+}
+
+impl AxisSource {
+    pub fn mouse_motion() -> Self {
+        AxisSource::Analog2D {
+            x: AnalogAxis::MouseX,
+            y: AnalogAxis::MouseY,
+        }
+    }
+
+    pub fn left_stick() -> Self {
+        AxisSource::Analog2D {
+            x: AnalogAxis::Gamepad(GamepadAxis::LeftStickX),
+            y: AnalogAxis::Gamepad(GamepadAxis::LeftStickY),
+        }
+    }
+
+    pub fn right_stick() -> Self {
+        AxisSource::Analog2D {
+            x: AnalogAxis::Gamepad(GamepadAxis::RightStickX),
+            y: AnalogAxis::Gamepad(GamepadAxis::RightStickY),
+        }
+    }
+
+    pub(crate) fn analog_axes(&self) -> impl Iterator<Item = AnalogAxis> {
+        let (x, y) = match *self {
+            AxisSource::Analog(axis) => (Some(axis), None),
+            AxisSource::Analog2D { x, y } => (Some(x), Some(y)),
+            AxisSource::Buttons { .. } | AxisSource::DirectionalButtons { .. } => (None, None),
+        };
+
+        x.into_iter().chain(y)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AxisSettings {
+    /// Values with a smaller magnitude are read as zero, larger ones are rescaled to start at zero.
+    /// Should be below 1.
+    pub deadzone: f32,
+    pub sensitivity: f32,
+    pub invert_x: bool,
+    pub invert_y: bool,
+    /// Limits the length of the value to 1, so that diagonal movement is not faster.
+    pub normalize: bool,
+}
+
+impl Default for AxisSettings {
+    fn default() -> Self {
+        Self {
+            deadzone: 0.0,
+            sensitivity: 1.0,
+            invert_x: false,
+            invert_y: false,
+            normalize: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AxisBinding {
+    pub source: AxisSource,
+    pub settings: AxisSettings,
+}
+
+impl AxisBinding {
+    pub fn new(source: AxisSource) -> Self {
+        Self {
+            source,
+            settings: AxisSettings::default(),
+        }
+    }
+
+    /// Value of the binding, one dimensional sources only set `x`.
+    pub(crate) fn value(
+        &self,
+        is_held: impl Fn(Input) -> bool,
+        analog: impl Fn(AnalogAxis) -> f32,
+    ) -> Vec2 {
+        let button = |input| if is_held(input) { 1.0 } else { 0.0 };
+
+        let raw = match self.source {
+            AxisSource::Buttons { negative, positive } => {
+                Vec2::new(button(positive) - button(negative), 0.0)
+            }
+            AxisSource::DirectionalButtons {
+                up,
+                down,
+                left,
+                right,
+            } => Vec2::new(button(right) - button(left), button(up) - button(down)),
+            AxisSource::Analog(axis) => Vec2::new(analog(axis), 0.0),
+            AxisSource::Analog2D { x, y } => Vec2::new(analog(x), analog(y)),
+        };
+
+        let settings = &self.settings;
+
+        let mut value = apply_deadzone(raw, settings.deadzone) * settings.sensitivity;
+
+        if settings.invert_x {
+            value.x = -value.x;
+        }
+
+        if settings.invert_y {
+            value.y = -value.y;
+        }
+
+        if settings.normalize {
+            value = value.clamp_length_max(1.0);
+        }
+
+        value
+    }
+}
+
+fn apply_deadzone(value: Vec2, deadzone: f32) -> Vec2 {
+    if deadzone <= 0.0 {
+        return value;
+    }
+
+    let length = value.length();
+
+    if length <= deadzone {
+        return Vec2::ZERO;
+    }
+
+    // Rescale so that lengths from the deadzone to 1 map to 0..1.
+    value / length * (length - deadzone) / (1.0 - deadzone).max(f32::EPSILON)
+}
+
+// Axis bindings are written as a source followed by settings that differ from the defaults, like
+// `directions(KeyW, KeyS, KeyA, KeyD) deadzone=0.2 sensitivity=2 invert_y normalize`. Sources are
+// `buttons(negative, positive)`, `directions(up, down, left, right)`, an analog axis like `MouseX`
+// or `GamepadLeftStickX`, and `analog(x, y)`.
+
+impl fmt::Display for AnalogAxis {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AnalogAxis::Gamepad(axis) => write!(f, "Gamepad{:?}", axis),
+            axis => write!(f, "{:?}", axis),
+        }
+    }
+}
+
+pub(crate) fn parse_analog_axis(name: &str) -> Option<AnalogAxis> {
+    match name {
+        "MouseX" => Some(AnalogAxis::MouseX),
+        "MouseY" => Some(AnalogAxis::MouseY),
+        "WheelX" => Some(AnalogAxis::WheelX),
+        "WheelY" => Some(AnalogAxis::WheelY),
+        name => parse_gamepad_axis(name.strip_prefix("Gamepad")?).map(AnalogAxis::Gamepad),
+    }
+}
+
+impl fmt::Display for AxisSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AxisSource::Buttons { negative, positive } => {
+                write!(f, "buttons({}, {})", negative, positive)
+            }
+            AxisSource::DirectionalButtons {
+                up,
+                down,
+                left,
+                right,
+            } => write!(f, "directions({}, {}, {}, {})", up, down, left, right),
+            AxisSource::Analog(axis) => write!(f, "{}", axis),
+            AxisSource::Analog2D { x, y } => write!(f, "analog({}, {})", x, y),
+        }
+    }
+}
+
+fn parse_axis_source(text: &str) -> Option<AxisSource> {
+    let Some((kind, args)) = text.split_once('(') else {
+        return parse_analog_axis(text).map(AxisSource::Analog);
+    };
+
+    let args: Vec<&str> = args.strip_suffix(')')?.split(',').map(str::trim).collect();
+
+    let source = match (kind.trim(), args.as_slice()) {
+        ("buttons", [negative, positive]) => AxisSource::Buttons {
+            negative: negative.parse().ok()?,
+            positive: positive.parse().ok()?,
+        },
+        ("directions", [up, down, left, right]) => AxisSource::DirectionalButtons {
+            up: up.parse().ok()?,
+            down: down.parse().ok()?,
+            left: left.parse().ok()?,
+            right: right.parse().ok()?,
+        },
+        ("analog", [x, y]) => AxisSource::Analog2D {
+            x: parse_analog_axis(x)?,
+            y: parse_analog_axis(y)?,
+        },
+        _ => return None,
+    };
+
+    Some(source)
+}
+
+impl fmt::Display for AxisBinding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let settings = &self.settings;
+        let default = AxisSettings::default();
+
+        write!(f, "{}", self.source)?;
+
+        if settings.deadzone != default.deadzone {
+            write!(f, " deadzone={}", settings.deadzone)?;
+        }
+
+        if settings.sensitivity != default.sensitivity {
+            write!(f, " sensitivity={}", settings.sensitivity)?;
+        }
+
+        if settings.invert_x {
+            write!(f, " invert_x")?;
+        }
+
+        if settings.invert_y {
+            write!(f, " invert_y")?;
+        }
+
+        if settings.normalize {
+            write!(f, " normalize")?;
+        }
+
+        Ok(())
+    }
+}
+
+pub(crate) fn parse_axis_binding(text: &str) -> Option<AxisBinding> {
+    let text = text.trim();
+
+    // The source ends with its arguments, which may contain spaces.
+    let source_end = match text.find('(') {
+        Some(_) => text.find(')')? + 1,
+        None => text.find(' ').unwrap_or(text.len()),
+    };
+
+    let mut binding = AxisBinding::new(parse_axis_source(&text[..source_end])?);
+    let settings = &mut binding.settings;
+
+    for setting in text[source_end..].split_whitespace() {
+        match setting.split_once('=') {
+            Some(("deadzone", value)) => settings.deadzone = value.parse().ok()?,
+            Some(("sensitivity", value)) => settings.sensitivity = value.parse().ok()?,
+            None if setting == "invert_x" => settings.invert_x = true,
+            None if setting == "invert_y" => settings.invert_y = true,
+            None if setting == "normalize" => settings.normalize = true,
+            _ => return None,
+        }
+    }
+
+    Some(binding)
+}
+
+#[cfg(test)]
+mod tests {
+    use winit::keyboard::KeyCode;
+
+    use super::*;
+
+    fn keys(held: &[KeyCode]) -> impl Fn(Input) -> bool {
+        move |input| matches!(input, Input::Key(key) if held.contains(&key))
+    }
+
+    fn directions() -> AxisBinding {
+        AxisBinding::new(AxisSource::DirectionalButtons {
+            up: Input::Key(KeyCode::KeyW),
+            down: Input::Key(KeyCode::KeyS),
+            left: Input::Key(KeyCode::KeyA),
+            right: Input::Key(KeyCode::KeyD),
+        })
+    }
+
+    #[test]
+    fn button_axes_combine_held_buttons() {
+        let no_analog = |_| 0.0;
+
+        let throttle = AxisBinding::new(AxisSource::Buttons {
+            negative: Input::Key(KeyCode::KeyS),
+            positive: Input::Key(KeyCode::KeyW),
+        });
+        assert_eq!(throttle.value(keys(&[KeyCode::KeyW]), no_analog), Vec2::X);
+        assert_eq!(
+            throttle.value(keys(&[KeyCode::KeyW, KeyCode::KeyS]), no_analog),
+            Vec2::ZERO
+        );
+
+        let mut moves = directions();
+        let diagonal = keys(&[KeyCode::KeyW, KeyCode::KeyA]);
+        assert_eq!(moves.value(&diagonal, no_analog), Vec2::new(-1.0, 1.0));
+
+        moves.settings.normalize = true;
+        let value = moves.value(&diagonal, no_analog);
+        assert!((value.length() - 1.0).abs() < 1e-6);
+        assert!(value.x < 0.0 && value.y > 0.0);
+    }
+
+    #[test]
+    fn analog_axes_apply_their_settings() {
+        let mut stick = AxisBinding::new(AxisSource::left_stick());
+        stick.settings.deadzone = 0.2;
+
+        let stick_at = |x, y| {
+            move |axis| match axis {
+                AnalogAxis::Gamepad(GamepadAxis::LeftStickX) => x,
+                AnalogAxis::Gamepad(GamepadAxis::LeftStickY) => y,
+                _ => 0.0,
+            }
+        };
+        let nothing_held = |_| false;
+
+        assert_eq!(stick.value(nothing_held, stick_at(0.1, 0.1)), Vec2::ZERO);
+        assert!(
+            stick
+                .value(nothing_held, stick_at(0.6, 0.0))
+                .abs_diff_eq(Vec2::new(0.5, 0.0), 1e-6)
+        );
+        assert!(
+            stick
+                .value(nothing_held, stick_at(0.0, -1.0))
+                .abs_diff_eq(Vec2::NEG_Y, 1e-6)
+        );
+
+        let mut look = AxisBinding::new(AxisSource::mouse_motion());
+        look.settings.sensitivity = 0.5;
+        look.settings.invert_y = true;
+
+        let motion = |axis| match axis {
+            AnalogAxis::MouseX => 4.0,
+            AnalogAxis::MouseY => 2.0,
+            _ => 0.0,
+        };
+        assert_eq!(look.value(nothing_held, motion), Vec2::new(2.0, -1.0));
+    }
+}
@@ -0,0 +1,537 @@
+use std::fmt;
+use std::ops::BitOr;
+use std::str::FromStr;
+
+use winit::event::MouseButton;
+use winit::keyboard::KeyCode;
+
+use crate::input::{GamepadAxis, GamepadButton, Input};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Modifiers {
+    pub ctrl: bool,
+    pub shift: bool,
+    pub alt: bool,
+    pub logo: bool,
+}
+
+impl Modifiers {
+    pub const NONE: Modifiers = Modifiers {
+        ctrl: false,
+        shift: false,
+        alt: false,
+        logo: false,
+    };
+    pub const CTRL: Modifiers = Modifiers {
+        ctrl: true,
+        ..Modifiers::NONE
+    };
+    pub const SHIFT: Modifiers = Modifiers {
+        shift: true,
+        ..Modifiers::NONE
+    };
+    pub const ALT: Modifiers = Modifiers {
+        alt: true,
+        ..Modifiers::NONE
+    };
+    pub const LOGO: Modifiers = Modifiers {
+        logo: true,
+        ..Modifiers::NONE
+    };
+
+    /// The modifier a key belongs to, if it is a modifier key.
+    pub fn of_key(key: KeyCode) -> Option<Modifiers> {
+        match key {
+            KeyCode::ControlLeft | KeyCode::ControlRight => Some(Modifiers::CTRL),
+            KeyCode::ShiftLeft | KeyCode::ShiftRight => Some(Modifiers::SHIFT),
+            KeyCode::AltLeft | KeyCode::AltRight => Some(Modifiers::ALT),
+            KeyCode::SuperLeft | KeyCode::SuperRight => Some(Modifiers::LOGO),
+            _ => None,
+        }
+    }
+
+    pub fn contains(&self, other: Modifiers) -> bool {
+        (self.ctrl || !other.ctrl)
+            && (self.shift || !other.shift)
+            && (self.alt || !other.alt)
+            && (self.logo || !other.logo)
+    }
+
+    pub fn without(&self, other: Modifiers) -> Modifiers {
+        Modifiers {
+            ctrl: self.ctrl && !other.ctrl,
+            shift: self.shift && !other.shift,
+            alt: self.alt && !other.alt,
+            logo: self.logo && !other.logo,
+        }
+    }
+
+    pub fn count(&self) -> usize {
+        [self.ctrl, self.shift, self.alt, self.logo]
+            .into_iter()
+            .filter(|held| *held)
+            .count()
+    }
+}
+
+impl BitOr for Modifiers {
+    type Output = Modifiers;
+
+    fn bitor(self, rhs: Modifiers) -> Modifiers {
+        Modifiers {
+            ctrl: self.ctrl || rhs.ctrl,
+            shift: self.shift || rhs.shift,
+            alt: self.alt || rhs.alt,
+            logo: self.logo || rhs.logo,
+        }
+    }
+}
+
+/// An input pressed while holding modifiers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Combo {
+    pub modifiers: Modifiers,
+    pub input: Input,
+}
+
+impl Combo {
+    pub fn new(modifiers: Modifiers, input: impl Into<Input>) -> Self {
+        Self {
+            modifiers,
+            input: input.into(),
+        }
+    }
+}
+
+/// What has to be pressed to trigger an action.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Binding {
+    /// Triggers while the input is held, if at least the combo's modifiers are held and no other
+    /// combo of the same input with more of the held modifiers is bound.
+    Combo(Combo),
+    /// Triggers when the last combo is pressed, if the ones before it were pressed in order with
+    /// exactly their modifiers held, each within the chord timeout of the previous one.
+    Chord(Vec<Combo>),
+}
+
+impl Binding {
+    pub fn chord(combos: impl IntoIterator<Item = impl Into<Combo>>) -> Self {
+        Binding::Chord(combos.into_iter().map(Into::into).collect())
+    }
+}
+
+impl From<Input> for Combo {
+    fn from(input: Input) -> Self {
+        Combo::new(Modifiers::NONE, input)
+    }
+}
+
+impl From<KeyCode> for Combo {
+    fn from(key: KeyCode) -> Self {
+        Combo::new(Modifiers::NONE, key)
+    }
+}
+
+impl From<MouseButton> for Combo {
+    fn from(button: MouseButton) -> Self {
+        Combo::new(Modifiers::NONE, button)
+    }
+}
+
+impl From<Combo> for Binding {
+    fn from(value: Combo) -> Self {
+        Binding::Combo(value)
+    }
+}
+
+impl From<Input> for Binding {
+    fn from(value: Input) -> Self {
+        Binding::Combo(value.into())
+    }
+}
+
+impl From<KeyCode> for Binding {
+    fn from(value: KeyCode) -> Self {
+        Binding::Combo(value.into())
+    }
+}
+
+impl From<MouseButton> for Binding {
+    fn from(value: MouseButton) -> Self {
+        Binding::Combo(value.into())
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum ParseBindingError {
+    #[error("empty binding")]
+    Empty,
+
+    #[error("unknown input: {0}")]
+    UnknownInput(String),
+}
+
+// Bindings are written as `Ctrl+Shift+KeyS`, with chords as combos separated by spaces. Keys are
+// named like their `KeyCode` variant, mouse buttons as `MouseLeft` or `Mouse4` and gamepad buttons
+// as `GamepadSouth`.
+
+impl fmt::Display for Input {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Input::Key(key) => write!(f, "{:?}", key),
+            Input::Mouse(MouseButton::Other(index)) => write!(f, "Mouse{}", index),
+            Input::Mouse(button) => write!(f, "Mouse{:?}", button),
+            Input::Gamepad(button) => write!(f, "Gamepad{:?}", button),
+        }
+    }
+}
+
+impl FromStr for Input {
+    type Err = ParseBindingError;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        let unknown = || ParseBindingError::UnknownInput(name.to_string());
+
+        if let Some(button) = name.strip_prefix("Mouse") {
+            let button = match button {
+                "Left" => MouseButton::Left,
+                "Right" => MouseButton::Right,
+                "Middle" => MouseButton::Middle,
+                "Back" => MouseButton::Back,
+                "Forward" => MouseButton::Forward,
+                index => MouseButton::Other(index.parse().map_err(|_| unknown())?),
+            };
+
+            return Ok(Input::Mouse(button));
+        }
+
+        if let Some(button) = name.strip_prefix("Gamepad") {
+            return parse_gamepad_button(button)
+                .map(Input::Gamepad)
+                .ok_or_else(unknown);
+        }
+
+        parse_key(name).map(Input::Key).ok_or_else(unknown)
+    }
+}
+
+const MODIFIER_NAMES: [(Modifiers, &str); 4] = [
+    (Modifiers::CTRL, "Ctrl"),
+    (Modifiers::SHIFT, "Shift"),
+    (Modifiers::ALT, "Alt"),
+    (Modifiers::LOGO, "Logo"),
+];
+
+impl fmt::Display for Combo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (modifier, name) in MODIFIER_NAMES {
+            if self.modifiers.contains(modifier) {
+                write!(f, "{}+", name)?;
+            }
+        }
+
+        write!(f, "{}", self.input)
+    }
+}
+
+impl FromStr for Combo {
+    type Err = ParseBindingError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut parts: Vec<&str> = text.split('+').map(str::trim).collect();
+        let input = parts.pop().filter(|input| !input.is_empty());
+        let input = input.ok_or(ParseBindingError::Empty)?.parse()?;
+
+        let mut modifiers = Modifiers::NONE;
+
+        for part in parts {
+            let (modifier, _) = MODIFIER_NAMES
+                .iter()
+                .find(|(_, name)| name.eq_ignore_ascii_case(part))
+                .ok_or_else(|| ParseBindingError::UnknownInput(part.to_string()))?;
+
+            modifiers = modifiers | *modifier;
+        }
+
+        Ok(Combo { modifiers, input })
+    }
+}
+
+impl fmt::Display for Binding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Binding::Combo(combo) => write!(f, "{}", combo),
+            Binding::Chord(combos) => {
+                for (index, combo) in combos.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, " ")?;
+                    }
+
+                    write!(f, "{}", combo)?;
+                }
+
+                Ok(())
+            }
+        }
+    }
+}
+
+impl FromStr for Binding {
+    type Err = ParseBindingError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut combos: Vec<Combo> = text
+            .split_whitespace()
+            .map(str::parse)
+            .collect::<Result<_, _>>()?;
+
+        match combos.len() {
+            0 => Err(ParseBindingError::Empty),
+            1 => Ok(Binding::Combo(combos.remove(0))),
+            _ => Ok(Binding::Chord(combos)),
+        }
+    }
+}
+
+macro_rules! variant_names {
+    ($parse:ident, $type:ident, $($variant:ident),* $(,)?) => {
+        pub(crate) fn $parse(name: &str) -> Option<$type> {
+            match name {
+                $(stringify!($variant) => Some($type::$variant),)*
+                _ => None,
+            }
+        }
+    };
+}
+
+variant_names!(
+    parse_gamepad_axis,
+    GamepadAxis,
+    LeftStickX,
+    LeftStickY,
+    RightStickX,
+    RightStickY,
+    LeftTrigger,
+    RightTrigger,
+);
+
+variant_names!(
+    parse_gamepad_button,
+    GamepadButton,
+    South,
+    East,
+    West,
+    North,
+    LeftBumper,
+    RightBumper,
+    LeftTrigger,
+    RightTrigger,
+    Select,
+    Start,
+    Guide,
+    LeftStick,
+    RightStick,
+    DPadUp,
+    DPadDown,
+    DPadLeft,
+    DPadRight,
+);
+
+variant_names!(
+    parse_key,
+    KeyCode,
+    Backquote,
+    Backslash,
+    BracketLeft,
+    BracketRight,
+    Comma,
+    Digit0,
+    Digit1,
+    Digit2,
+    Digit3,
+    Digit4,
+    Digit5,
+    Digit6,
+    Digit7,
+    Digit8,
+    Digit9,
+    Equal,
+    IntlBackslash,
+    IntlRo,
+    IntlYen,
+    KeyA,
+    KeyB,
+    KeyC,
+    KeyD,
+    KeyE,
+    KeyF,
+    KeyG,
+    KeyH,
+    KeyI,
+    KeyJ,
+    KeyK,
+    KeyL,
+    KeyM,
+    KeyN,
+    KeyO,
+    KeyP,
+    KeyQ,
+    KeyR,
+    KeyS,
+    KeyT,
+    KeyU,
+    KeyV,
+    KeyW,
+    KeyX,
+    KeyY,
+    KeyZ,
+    Minus,
+    Period,
+    Quote,
+    Semicolon,
+    Slash,
+    AltLeft,
+    AltRight,
+    Backspace,
+    CapsLock,
+    ContextMenu,
+    ControlLeft,
+    ControlRight,
+    Enter,
+    SuperLeft,
+    SuperRight,
+    ShiftLeft,
+    ShiftRight,
+    Space,
+    Tab,
+    Convert,
+    KanaMode,
+    Lang1,
+    Lang2,
+    Lang3,
+    Lang4,
+    Lang5,
+    NonConvert,
+    Delete,
+    End,
+    Help,
+    Home,
+    Insert,
+    PageDown,
+    PageUp,
+    ArrowDown,
+    ArrowLeft,
+    ArrowRight,
+    ArrowUp,
+    NumLock,
+    Numpad0,
+    Numpad1,
+    Numpad2,
+    Numpad3,
+    Numpad4,
+    Numpad5,
+    Numpad6,
+    Numpad7,
+    Numpad8,
+    Numpad9,
+    NumpadAdd,
+    NumpadBackspace,
+    NumpadClear,
+    NumpadClearEntry,
+    NumpadComma,
+    NumpadDecimal,
+    NumpadDivide,
+    NumpadEnter,
+    NumpadEqual,
+    NumpadHash,
+    NumpadMemoryAdd,
+    NumpadMemoryClear,
+    NumpadMemoryRecall,
+    NumpadMemoryStore,
+    NumpadMemorySubtract,
+    NumpadMultiply,
+    NumpadParenLeft,
+    NumpadParenRight,
+    NumpadStar,
+    NumpadSubtract,
+    Escape,
+    Fn,
+    FnLock,
+    PrintScreen,
+    ScrollLock,
+    Pause,
+    BrowserBack,
+    BrowserFavorites,
+    BrowserForward,
+    BrowserHome,
+    BrowserRefresh,
+    BrowserSearch,
+    BrowserStop,
+    Eject,
+    LaunchApp1,
+    LaunchApp2,
+    LaunchMail,
+    MediaPlayPause,
+    MediaSelect,
+    MediaStop,
+    MediaTrackNext,
+    MediaTrackPrevious,
+    Power,
+    Sleep,
+    AudioVolumeDown,
+    AudioVolumeMute,
+    AudioVolumeUp,
+    WakeUp,
+    Meta,
+    Hyper,
+    Turbo,
+    Abort,
+    Resume,
+    Suspend,
+    Again,
+    Copy,
+    Cut,
+    Find,
+    Open,
+    Paste,
+    Props,
+    Select,
+    Undo,
+    Hiragana,
+    Katakana,
+    F1,
+    F2,
+    F3,
+    F4,
+    F5,
+    F6,
+    F7,
+    F8,
+    F9,
+    F10,
+    F11,
+    F12,
+    F13,
+    F14,
+    F15,
+    F16,
+    F17,
+    F18,
+    F19,
+    F20,
+    F21,
+    F22,
+    F23,
+    F24,
+    F25,
+    F26,
+    F27,
+    F28,
+    F29,
+    F30,
+    F31,
+    F32,
+    F33,
+    F34,
+    F35,
+);
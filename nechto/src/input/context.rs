@@ -0,0 +1,53 @@
+use crate::input::InputMap;
+
+/// Which presses a context hides from the contexts below it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Consumption {
+    /// Lower contexts see every press.
+    None,
+    /// Lower contexts don't see presses that trigger actions of this context, and analog axes
+    /// read by its axis bindings.
+    #[default]
+    Bound,
+    /// Lower contexts don't see any input, like a modal console.
+    All,
+}
+
+/// Named bindings pushed on top of the input map of `InputHandler`, for example for a console or
+/// editor UI. Contexts with a higher priority resolve presses first, among equal priorities the
+/// last pushed context does.
+#[derive(Debug, Clone)]
+pub struct InputContext {
+    name: String,
+    pub priority: i32,
+    pub consumption: Consumption,
+    /// Disabled contexts neither trigger nor consume.
+    pub enabled: bool,
+    pub map: InputMap,
+}
+
+impl InputContext {
+    pub fn new(name: impl Into<String>, priority: i32) -> Self {
+        Self {
+            name: name.into(),
+            priority,
+            consumption: Consumption::default(),
+            enabled: true,
+            map: InputMap::new(),
+        }
+    }
+
+    pub fn with_consumption(mut self, consumption: Consumption) -> Self {
+        self.consumption = consumption;
+        self
+    }
+
+    pub fn with_map(mut self, map: InputMap) -> Self {
+        self.map = map;
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
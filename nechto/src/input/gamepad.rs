@@ -0,0 +1,175 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::input::GamepadAxis;
+
+/// Identifies a connected gamepad, assigned by the backend.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct GamepadId(pub u32);
+
+/// Buttons named by their position, so that bindings work with any controller layout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GamepadButton {
+    South,
+    East,
+    West,
+    North,
+    LeftBumper,
+    RightBumper,
+    LeftTrigger,
+    RightTrigger,
+    Select,
+    Start,
+    Guide,
+    LeftStick,
+    RightStick,
+    DPadUp,
+    DPadDown,
+    DPadLeft,
+    DPadRight,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum GamepadEvent {
+    Connected {
+        id: GamepadId,
+        name: String,
+    },
+    Disconnected {
+        id: GamepadId,
+    },
+    Button {
+        id: GamepadId,
+        button: GamepadButton,
+        pressed: bool,
+    },
+    Axis {
+        id: GamepadId,
+        axis: GamepadAxis,
+        value: f32,
+    },
+}
+
+/// Vibration of the low frequency (`strong`) and high frequency (`weak`) motors in `0..1`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rumble {
+    pub strong: f32,
+    pub weak: f32,
+    pub duration: Duration,
+}
+
+/// Source of device input that winit doesn't provide.
+pub trait InputBackend {
+    /// Appends the events that happened since the previous call.
+    fn poll(&mut self, events: &mut Vec<GamepadEvent>);
+
+    fn rumble(&mut self, id: GamepadId, rumble: Rumble);
+}
+
+/// A connected gamepad and the player it belongs to.
+#[derive(Debug, Clone)]
+pub struct Gamepad {
+    pub id: GamepadId,
+    pub name: String,
+    /// Only gamepads assigned to a player trigger actions.
+    pub player: Option<usize>,
+    pub(crate) buttons: HashSet<GamepadButton>,
+    pub(crate) axes: HashMap<GamepadAxis, f32>,
+}
+
+impl Gamepad {
+    pub(crate) fn new(id: GamepadId, name: String, player: Option<usize>) -> Self {
+        Self {
+            id,
+            name,
+            player,
+            buttons: HashSet::new(),
+            axes: HashMap::new(),
+        }
+    }
+
+    pub fn is_pressed(&self, button: GamepadButton) -> bool {
+        self.buttons.contains(&button)
+    }
+
+    pub fn axis(&self, axis: GamepadAxis) -> f32 {
+        self.axes.get(&axis).copied().unwrap_or(0.0)
+    }
+}
+
+#[derive(Default)]
+struct VirtualState {
+    next_id: u32,
+    events: Vec<GamepadEvent>,
+    rumbles: Vec<(GamepadId, Rumble)>,
+}
+
+/// Gamepads controlled from code, for tests and scripted input.
+///
+/// Clones share the same gamepads, so one clone can be given to `InputHandler::set_backend`
+/// while another drives it.
+#[derive(Clone, Default)]
+pub struct VirtualGamepads(Arc<Mutex<VirtualState>>);
+
+impl VirtualGamepads {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn connect(&self, name: impl Into<String>) -> GamepadId {
+        let mut state = self.0.lock().unwrap();
+
+        let id = GamepadId(state.next_id);
+        state.next_id += 1;
+        state.events.push(GamepadEvent::Connected {
+            id,
+            name: name.into(),
+        });
+
+        id
+    }
+
+    pub fn disconnect(&self, id: GamepadId) {
+        self.push(GamepadEvent::Disconnected { id });
+    }
+
+    pub fn press(&self, id: GamepadId, button: GamepadButton) {
+        self.push(GamepadEvent::Button {
+            id,
+            button,
+            pressed: true,
+        });
+    }
+
+    pub fn release(&self, id: GamepadId, button: GamepadButton) {
+        self.push(GamepadEvent::Button {
+            id,
+            button,
+            pressed: false,
+        });
+    }
+
+    pub fn set_axis(&self, id: GamepadId, axis: GamepadAxis, value: f32) {
+        self.push(GamepadEvent::Axis { id, axis, value });
+    }
+
+    /// Returns the rumble requests received since the previous call.
+    pub fn take_rumbles(&self) -> Vec<(GamepadId, Rumble)> {
+        std::mem::take(&mut self.0.lock().unwrap().rumbles)
+    }
+
+    fn push(&self, event: GamepadEvent) {
+        self.0.lock().unwrap().events.push(event);
+    }
+}
+
+impl InputBackend for VirtualGamepads {
+    fn poll(&mut self, events: &mut Vec<GamepadEvent>) {
+        events.append(&mut self.0.lock().unwrap().events);
+    }
+
+    fn rumble(&mut self, id: GamepadId, rumble: Rumble) {
+        self.0.lock().unwrap().rumbles.push((id, rumble));
+    }
+}
@@ -0,0 +1,51 @@
+use std::time::{Duration, Instant};
+
+use crate::input::Action;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ActionPhase {
+    /// An input of the action was pressed while none were held.
+    Started,
+    /// The interaction of the action completed.
+    Performed,
+    /// The interaction failed, or the input of a `Press` or `Hold` action was released.
+    Cancelled,
+    /// The key of an active action is repeating.
+    Repeated,
+}
+
+#[derive(Debug, Clone)]
+pub struct ActionEvent {
+    pub action: Action,
+    pub phase: ActionPhase,
+    pub time: Instant,
+}
+
+/// How pressing and releasing the inputs of an action performs it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Interaction {
+    /// Performed on press, cancelled on release.
+    #[default]
+    Press,
+    /// Performed after being held for the duration, cancelled on release.
+    Hold(Duration),
+    /// Performed on release within the duration, cancelled when held longer.
+    Tap(Duration),
+    /// Performed on a second press within the duration after the first, cancelled otherwise.
+    DoubleTap(Duration),
+}
+
+/// Progress of a started `Hold`, `Tap` or `DoubleTap` interaction.
+pub(crate) struct InteractionState {
+    pub started: Instant,
+    pub performed: bool,
+}
+
+impl InteractionState {
+    pub fn new(started: Instant) -> Self {
+        Self {
+            started,
+            performed: false,
+        }
+    }
+}
@@ -0,0 +1,243 @@
+use std::fmt;
+
+use crate::input::axis::parse_axis_binding;
+use crate::input::{Action, AnalogAxis, AxisBinding, Binding, ParseBindingError};
+use crate::vfs::{self, IntoPathSpec, VirtualFs};
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("line {line}: expected `action = binding, ...`")]
+    InvalidLine { line: usize },
+
+    #[error("line {line}: {source}")]
+    InvalidBinding {
+        line: usize,
+        source: ParseBindingError,
+    },
+
+    #[error("line {line}: expected `action = source setting ...`")]
+    InvalidAxis { line: usize },
+
+    #[error("input map is not valid UTF-8")]
+    InvalidUtf8,
+
+    #[error(transparent)]
+    Vfs(#[from] vfs::Error),
+}
+
+/// Bindings of actions, stored as lines of `action = binding, binding` with `#` comments, followed
+/// by an `[axes]` section with one `action = source setting ...` line per axis binding.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct InputMap {
+    bindings: Vec<(Binding, Action)>,
+    axes: Vec<(AxisBinding, Action)>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Section {
+    Actions,
+    Axes,
+}
+
+impl InputMap {
+    pub fn new() -> Self {
+        Self {
+            bindings: Vec::new(),
+            axes: Vec::new(),
+        }
+    }
+
+    pub fn add(&mut self, binding: impl Into<Binding>, action: Action) {
+        self.bindings.push((binding.into(), action));
+    }
+
+    pub fn remove(&mut self, action: &Action) {
+        self.bindings.retain(|(_, bound)| bound != action);
+    }
+
+    /// Adds a source to an axis action, the values of all sources of an action are summed.
+    pub fn add_axis(&mut self, binding: AxisBinding, action: Action) {
+        self.axes.push((binding, action));
+    }
+
+    pub fn remove_axes(&mut self, action: &Action) {
+        self.axes.retain(|(_, bound)| bound != action);
+    }
+
+    /// Analog axes that axis bindings of any action read.
+    pub(crate) fn analog_axes(&self) -> impl Iterator<Item = AnalogAxis> {
+        self.axes
+            .iter()
+            .flat_map(|(binding, _)| binding.source.analog_axes())
+    }
+
+    pub fn axes<'a>(&'a self, action: &'a Action) -> impl Iterator<Item = &'a AxisBinding> {
+        self.axes
+            .iter()
+            .filter(move |(_, bound)| bound == action)
+            .map(|(binding, _)| binding)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&Binding, &Action)> {
+        self.bindings
+            .iter()
+            .map(|(binding, action)| (binding, action))
+    }
+
+    pub fn bindings<'a>(&'a self, action: &'a Action) -> impl Iterator<Item = &'a Binding> {
+        self.iter()
+            .filter(move |(_, bound)| *bound == action)
+            .map(|(binding, _)| binding)
+    }
+
+    pub fn parse(text: &str) -> Result<Self, Error> {
+        let mut map = InputMap::new();
+        let mut section = Section::Actions;
+
+        for (index, line) in text.lines().enumerate() {
+            let line = line.trim();
+
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            if line.starts_with('[') {
+                section = match line {
+                    "[actions]" => Section::Actions,
+                    "[axes]" => Section::Axes,
+                    _ => return Err(Error::InvalidLine { line: index + 1 }),
+                };
+
+                continue;
+            }
+
+            let Some((action, bindings)) = line.split_once('=') else {
+                return Err(Error::InvalidLine { line: index + 1 });
+            };
+
+            let action = Action::new(action.trim());
+
+            if section == Section::Axes {
+                let binding =
+                    parse_axis_binding(bindings).ok_or(Error::InvalidAxis { line: index + 1 })?;
+
+                map.axes.push((binding, action));
+                continue;
+            }
+
+            for binding in bindings.split(',') {
+                let binding = binding.parse().map_err(|source| Error::InvalidBinding {
+                    line: index + 1,
+                    source,
+                })?;
+
+                map.bindings.push((binding, action.clone()));
+            }
+        }
+
+        Ok(map)
+    }
+
+    pub fn load(vfs: &VirtualFs, path: impl IntoPathSpec) -> Result<Self, Error> {
+        let data = vfs.read(path)?;
+        let text = String::from_utf8(data).map_err(|_| Error::InvalidUtf8)?;
+
+        Self::parse(&text)
+    }
+
+    pub fn save(&self, vfs: &VirtualFs, path: impl IntoPathSpec) -> Result<(), Error> {
+        vfs.write(path, self.to_string())?;
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for InputMap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut actions: Vec<&Action> = Vec::new();
+
+        for (_, action) in &self.bindings {
+            if !actions.contains(&action) {
+                actions.push(action);
+            }
+        }
+
+        for action in actions {
+            let bindings: Vec<String> = self.bindings(action).map(Binding::to_string).collect();
+
+            writeln!(f, "{} = {}", action.name(), bindings.join(", "))?;
+        }
+
+        if !self.axes.is_empty() {
+            if !self.bindings.is_empty() {
+                writeln!(f)?;
+            }
+
+            writeln!(f, "[axes]")?;
+
+            for (binding, action) in &self.axes {
+                writeln!(f, "{} = {}", action.name(), binding)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::{AnalogAxis, AxisSource, GamepadAxis, Input};
+
+    #[test]
+    fn axes_round_trip() {
+        let text = "\
+jump = Space, GamepadSouth
+
+[axes]
+move = directions(KeyW, KeyS, KeyA, KeyD) normalize
+move = analog(GamepadLeftStickX, GamepadLeftStickY) deadzone=0.2
+look = analog(MouseX, MouseY) sensitivity=0.1 invert_y
+throttle = buttons(KeyS, KeyW)
+";
+        let map = InputMap::parse(text).unwrap();
+        let move_action = Action::new("move");
+        let moves: Vec<&AxisBinding> = map.axes(&move_action).collect();
+
+        assert_eq!(moves.len(), 2);
+        assert!(moves[0].settings.normalize);
+        assert_eq!(
+            moves[1].source,
+            AxisSource::Analog2D {
+                x: AnalogAxis::Gamepad(GamepadAxis::LeftStickX),
+                y: AnalogAxis::Gamepad(GamepadAxis::LeftStickY),
+            }
+        );
+        assert_eq!(moves[1].settings.deadzone, 0.2);
+
+        let throttle_action = Action::new("throttle");
+        let throttle = map.axes(&throttle_action).next().unwrap();
+        assert_eq!(
+            throttle.source,
+            AxisSource::Buttons {
+                negative: Input::Key(winit::keyboard::KeyCode::KeyS),
+                positive: Input::Key(winit::keyboard::KeyCode::KeyW),
+            }
+        );
+
+        assert_eq!(map.to_string(), text);
+        assert_eq!(InputMap::parse(&map.to_string()).unwrap(), map);
+    }
+
+    #[test]
+    fn invalid_axes_are_rejected() {
+        for text in [
+            "[axes]\nmove = directions(KeyW, KeyS)",
+            "[axes]\nlook = MouseZ",
+            "[axes]\nlook = MouseX deadzone=wide",
+            "[buttons]\njump = Space",
+        ] {
+            assert!(InputMap::parse(text).is_err(), "{text}");
+        }
+    }
+}
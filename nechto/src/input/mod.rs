@@ -1,9 +1,44 @@
+mod axis;
+mod binding;
+mod context;
+mod gamepad;
+mod interaction;
+mod map;
+mod record;
+mod text;
+
+pub use self::axis::{AnalogAxis, AxisBinding, AxisSettings, AxisSource, GamepadAxis};
+pub use self::binding::{Binding, Combo, Modifiers, ParseBindingError};
+pub use self::context::{Consumption, InputContext};
+pub use self::gamepad::{
+    Gamepad, GamepadButton, GamepadEvent, GamepadId, InputBackend, Rumble, VirtualGamepads,
+};
+pub use self::interaction::{ActionEvent, ActionPhase, Interaction};
+pub use self::map::{Error as InputMapError, InputMap};
+pub use self::record::{Error as RecordingError, RawInput, RecordedFrame, Recording};
+pub use self::text::TextEvent;
+
+use std::cmp::Reverse;
 use std::collections::{HashMap, HashSet};
+use std::ops::BitOr;
+use std::time::{Duration, Instant};
 
-use winit::event::{ElementState, KeyEvent};
+use glam::Vec2;
+use winit::dpi::{PhysicalPosition, PhysicalSize};
+use winit::event::{ElementState, Ime, KeyEvent, MouseButton, MouseScrollDelta};
 use winit::keyboard::{KeyCode, PhysicalKey};
 
-#[derive(Clone, PartialEq, Eq, Hash)]
+use tracing::info;
+
+use crate::input::interaction::InteractionState;
+use crate::input::record::{Recorder, Replay};
+
+/// Scroll distance in pixels that counts as one line for `InputHandler::scroll_delta`.
+const PIXELS_PER_LINE: f32 = 20.0;
+
+const DEFAULT_CHORD_TIMEOUT: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Action {
     name: String,
 }
@@ -18,23 +53,188 @@ impl Action {
     }
 }
 
+/// A button that can be bound to an action.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Input {
+    Key(KeyCode),
+    Mouse(MouseButton),
+    /// Pressed on any gamepad assigned to a player.
+    Gamepad(GamepadButton),
+}
+
+impl From<KeyCode> for Input {
+    fn from(key: KeyCode) -> Self {
+        Input::Key(key)
+    }
+}
+
+impl From<MouseButton> for Input {
+    fn from(button: MouseButton) -> Self {
+        Input::Mouse(button)
+    }
+}
+
+impl From<GamepadButton> for Input {
+    fn from(button: GamepadButton) -> Self {
+        Input::Gamepad(button)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CursorMode {
+    #[default]
+    Normal,
+    /// Hidden while over the window.
+    Hidden,
+    /// Kept inside the window.
+    Confined,
+    /// Hidden and kept in place, for camera control with `InputHandler::mouse_motion`.
+    Locked,
+}
+
 pub struct InputHandler {
-    keys_to_actions: HashMap<KeyCode, Action>,
-    action_queue: Vec<Action>,
-    active_actions: HashSet<Action>,
+    /// Bindings below all contexts.
+    map: InputMap,
+    contexts: Vec<InputContext>,
+    action_queue: Vec<ActionEvent>,
+    interactions: HashMap<Action, Interaction>,
+    interaction_states: HashMap<Action, InteractionState>,
+    just_pressed: Vec<Action>,
+    just_released: Vec<Action>,
+    /// Number of held inputs triggering each active action.
+    active_actions: HashMap<Action, usize>,
+    held_inputs: HashSet<Input>,
+    /// Held inputs whose press a context consumed, they don't drive axes.
+    consumed_inputs: HashSet<Input>,
+    /// Actions triggered by pressing each held input, released with it.
+    input_actions: HashMap<Input, Vec<Action>>,
+    chord_progress: Vec<Combo>,
+    chord_updated: Instant,
+    chord_timeout: Duration,
+    capture: Capture,
+    backend: Option<Box<dyn InputBackend>>,
+    gamepads: Vec<Gamepad>,
+    window_size: Vec2,
+    cursor_position: Option<Vec2>,
+    scroll_delta: Vec2,
+    mouse_motion: Vec2,
+    cursor_mode: CursorMode,
+    cursor_mode_changed: bool,
+    text_mode: bool,
+    text_mode_changed: bool,
+    text_events: Vec<TextEvent>,
+    /// Number of `update` calls so far.
+    frame: u64,
+    recorder: Option<Recorder>,
+    replay: Option<Replay>,
 }
 
 impl InputHandler {
     pub fn new() -> Self {
         Self {
-            keys_to_actions: HashMap::new(),
+            map: InputMap::new(),
+            contexts: Vec::new(),
             action_queue: Vec::new(),
-            active_actions: HashSet::new(),
+            interactions: HashMap::new(),
+            interaction_states: HashMap::new(),
+            just_pressed: Vec::new(),
+            just_released: Vec::new(),
+            active_actions: HashMap::new(),
+            held_inputs: HashSet::new(),
+            consumed_inputs: HashSet::new(),
+            input_actions: HashMap::new(),
+            chord_progress: Vec::new(),
+            chord_updated: Instant::now(),
+            chord_timeout: DEFAULT_CHORD_TIMEOUT,
+            capture: Capture::Off,
+            backend: None,
+            gamepads: Vec::new(),
+            window_size: Vec2::ZERO,
+            cursor_position: None,
+            scroll_delta: Vec2::ZERO,
+            mouse_motion: Vec2::ZERO,
+            cursor_mode: CursorMode::Normal,
+            cursor_mode_changed: false,
+            text_mode: false,
+            text_mode_changed: false,
+            text_events: Vec::new(),
+            frame: 0,
+            recorder: None,
+            replay: None,
+        }
+    }
+
+    /// Binds an action, an action can have many bindings and a binding can trigger many actions.
+    pub fn add_action(&mut self, binding: impl Into<Binding>, action: Action) {
+        self.map.add(binding, action);
+    }
+
+    /// Removes all bindings of an action.
+    pub fn remove_action(&mut self, action: &Action) {
+        self.map.remove(action);
+    }
+
+    /// Sets how the action is performed, `Interaction::Press` by default.
+    pub fn set_interaction(&mut self, action: Action, interaction: Interaction) {
+        self.interaction_states.remove(&action);
+        self.interactions.insert(action, interaction);
+    }
+
+    /// Replaces the bindings of an action.
+    pub fn rebind(&mut self, action: &Action, bindings: impl IntoIterator<Item = Binding>) {
+        self.map.remove(action);
+
+        for binding in bindings {
+            self.map.add(binding, action.clone());
+        }
+    }
+
+    pub fn bindings<'a>(&'a self, action: &'a Action) -> impl Iterator<Item = &'a Binding> {
+        self.map.bindings(action)
+    }
+
+    pub fn input_map(&self) -> &InputMap {
+        &self.map
+    }
+
+    /// Replaces all bindings, actions of held inputs stay active until they are released.
+    pub fn set_input_map(&mut self, map: InputMap) {
+        self.map = map;
+        self.chord_progress.clear();
+    }
+
+    /// Makes the next pressed input (with the modifiers held) available from
+    /// `take_captured_combo` instead of triggering actions, for rebinding in settings screens.
+    pub fn capture_next_combo(&mut self) {
+        self.capture = Capture::Waiting;
+    }
+
+    pub fn cancel_capture(&mut self) {
+        self.capture = Capture::Off;
+    }
+
+    pub fn is_capturing(&self) -> bool {
+        matches!(self.capture, Capture::Waiting)
+    }
+
+    pub fn take_captured_combo(&mut self) -> Option<Combo> {
+        match std::mem::replace(&mut self.capture, Capture::Off) {
+            Capture::Captured(combo) => Some(combo),
+            capture => {
+                self.capture = capture;
+                None
+            }
         }
     }
 
-    pub fn add_action(&mut self, key: KeyCode, action: Action) {
-        self.keys_to_actions.insert(key, action);
+    /// Sets the longest time between presses of consecutive combos of a chord.
+    pub fn set_chord_timeout(&mut self, timeout: Duration) {
+        self.chord_timeout = timeout;
+    }
+
+    /// Adds a source to an axis action, the values of all sources of an action are summed.
+    pub fn add_axis(&mut self, action: Action, binding: AxisBinding) {
+        self.map.add_axis(binding, action);
     }
 
     pub fn submit_key_event(&mut self, event: KeyEvent) {
@@ -42,31 +242,1649 @@ impl InputHandler {
             return;
         };
 
-        let Some(action) = self.keys_to_actions.get(&keycode) else {
+        self.submit_key(keycode, event.state, event.text.as_deref());
+    }
+
+    fn submit_key(&mut self, keycode: KeyCode, state: ElementState, text: Option<&str>) {
+        // Typing into a text field must not trigger actions, except those of contexts above it
+        // like closing a console. Modifiers are still held for the combos of those contexts, and
+        // releases still end held actions.
+        let is_modifier = Modifiers::of_key(keycode).is_some();
+
+        if self.text_mode
+            && state == ElementState::Pressed
+            && (is_modifier || !self.is_bound_by_context(Input::Key(keycode)))
+        {
+            self.submit_raw(RawInput::Text(TextEvent::Key(keycode)));
+
+            if let Some(text) = text {
+                let text: String = text.chars().filter(|c| !c.is_control()).collect();
+
+                if !text.is_empty() {
+                    self.submit_raw(RawInput::Text(TextEvent::Text(text)));
+                }
+            }
+
+            if !is_modifier {
+                return;
+            }
+        }
+
+        self.submit_button(Input::Key(keycode), state);
+    }
+
+    pub fn submit_ime(&mut self, ime: Ime) {
+        if !self.text_mode {
             return;
-        };
+        }
 
-        match event.state {
+        match ime {
+            Ime::Preedit(text, cursor) => {
+                self.submit_raw(RawInput::Text(TextEvent::Preedit { text, cursor }))
+            }
+            Ime::Commit(text) => self.submit_raw(RawInput::Text(TextEvent::Text(text))),
+            Ime::Enabled | Ime::Disabled => {}
+        }
+    }
+
+    pub fn submit_mouse_button(&mut self, button: MouseButton, state: ElementState) {
+        self.submit_button(Input::Mouse(button), state);
+    }
+
+    fn submit_button(&mut self, input: Input, state: ElementState) {
+        self.submit_raw(match state {
+            ElementState::Pressed => RawInput::Press(input),
+            ElementState::Released => RawInput::Release(input),
+        });
+    }
+
+    /// Records and applies live input, which is ignored while replaying.
+    fn submit_raw(&mut self, input: RawInput) {
+        if self.replay.is_some() {
+            return;
+        }
+
+        let now = self.now();
+
+        if let Some(recorder) = &mut self.recorder {
+            recorder.record(now, input.clone());
+        }
+
+        self.apply_raw(input);
+    }
+
+    fn apply_raw(&mut self, input: RawInput) {
+        match input {
+            RawInput::Press(input) => self.submit_input(input, ElementState::Pressed),
+            RawInput::Release(input) => self.submit_input(input, ElementState::Released),
+            RawInput::CursorMoved(position) => self.cursor_position = Some(position),
+            RawInput::CursorLeft => self.cursor_position = None,
+            RawInput::MouseWheel(delta) => self.scroll_delta += delta,
+            RawInput::MouseMotion(delta) => self.mouse_motion += delta,
+            RawInput::WindowSize(size) => self.window_size = size,
+            RawInput::Gamepad(event) => self.apply_gamepad_event(event),
+            RawInput::AssignPlayer { id, player } => self.apply_player(id, player),
+            RawInput::Text(event) => self.text_events.push(event),
+        }
+    }
+
+    /// The recorded time while replaying, the current time otherwise.
+    fn now(&self) -> Instant {
+        self.replay
+            .as_ref()
+            .map_or_else(Instant::now, |replay| replay.now())
+    }
+
+    fn submit_input(&mut self, input: Input, state: ElementState) {
+        let time = self.now();
+
+        match state {
             ElementState::Pressed => {
-                self.active_actions.insert(action.clone());
+                if !self.held_inputs.insert(input) {
+                    // Key repeat, the actions of the input are still active.
+                    for action in self.input_actions.get(&input).into_iter().flatten() {
+                        self.action_queue.push(ActionEvent {
+                            action: action.clone(),
+                            phase: ActionPhase::Repeated,
+                            time,
+                        });
+                    }
+
+                    return;
+                }
+
+                let actions = self.resolve_press(input);
+
+                for action in &actions {
+                    let count = self.active_actions.entry(action.clone()).or_default();
+                    *count += 1;
+
+                    if *count == 1 {
+                        self.start_action(action.clone(), time);
+                    }
+                }
+
+                self.input_actions.insert(input, actions);
             }
             ElementState::Released => {
-                self.active_actions.remove(action);
+                self.held_inputs.remove(&input);
+                self.consumed_inputs.remove(&input);
+
+                for action in self.input_actions.remove(&input).unwrap_or_default() {
+                    let Some(count) = self.active_actions.get_mut(&action) else {
+                        continue;
+                    };
+
+                    *count -= 1;
+
+                    if *count == 0 {
+                        self.active_actions.remove(&action);
+                        self.end_action(action, time);
+                    }
+                }
             }
         }
+    }
 
-        self.action_queue.push(action.clone());
+    fn push_event(&mut self, action: Action, phase: ActionPhase, time: Instant) {
+        self.action_queue.push(ActionEvent {
+            action,
+            phase,
+            time,
+        });
     }
 
-    pub fn actions(&mut self) -> impl Iterator<Item = &Action> {
+    /// Called when the first held input of an action is pressed.
+    fn start_action(&mut self, action: Action, time: Instant) {
+        if !self.just_pressed.contains(&action) {
+            self.just_pressed.push(action.clone());
+        }
+
+        let interaction = self.interactions.get(&action).copied().unwrap_or_default();
+
+        match interaction {
+            Interaction::Press => {
+                self.push_event(action.clone(), ActionPhase::Started, time);
+                self.push_event(action, ActionPhase::Performed, time);
+            }
+            Interaction::Hold(_) | Interaction::Tap(_) => {
+                self.interaction_states
+                    .insert(action.clone(), InteractionState::new(time));
+                self.push_event(action, ActionPhase::Started, time);
+            }
+            Interaction::DoubleTap(duration) => {
+                let first_tap = self.interaction_states.remove(&action);
+
+                match first_tap {
+                    Some(state) if time.duration_since(state.started) <= duration => {
+                        self.push_event(action, ActionPhase::Performed, time);
+                    }
+                    _ => {
+                        self.interaction_states
+                            .insert(action.clone(), InteractionState::new(time));
+                        self.push_event(action, ActionPhase::Started, time);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Called when the last held input of an action is released.
+    fn end_action(&mut self, action: Action, time: Instant) {
+        if !self.just_released.contains(&action) {
+            self.just_released.push(action.clone());
+        }
+
+        let interaction = self.interactions.get(&action).copied().unwrap_or_default();
+
+        match interaction {
+            Interaction::Press => {
+                self.push_event(action, ActionPhase::Cancelled, time);
+            }
+            Interaction::Hold(_) => {
+                self.interaction_states.remove(&action);
+                self.push_event(action, ActionPhase::Cancelled, time);
+            }
+            Interaction::Tap(duration) => {
+                // A tap held too long was already cancelled by `update`.
+                if let Some(state) = self.interaction_states.remove(&action)
+                    && time.duration_since(state.started) <= duration
+                {
+                    self.push_event(action, ActionPhase::Performed, time);
+                }
+            }
+            Interaction::DoubleTap(_) => {}
+        }
+    }
+
+    /// Performs and cancels interactions that depend on time passing without input, call once
+    /// per frame before reading actions.
+    pub fn update(&mut self) {
+        self.replay_frame();
+
+        let now = self.now();
+
+        let mut events = Vec::new();
+
+        self.interaction_states.retain(|action, state| {
+            let interaction = self.interactions.get(action).copied().unwrap_or_default();
+            let elapsed = now.duration_since(state.started);
+
+            match interaction {
+                Interaction::Hold(duration) if !state.performed && elapsed >= duration => {
+                    state.performed = true;
+                    events.push((action.clone(), ActionPhase::Performed));
+                    true
+                }
+                Interaction::Tap(duration) | Interaction::DoubleTap(duration)
+                    if elapsed > duration =>
+                {
+                    events.push((action.clone(), ActionPhase::Cancelled));
+                    false
+                }
+                _ => true,
+            }
+        });
+
+        for (action, phase) in events {
+            self.push_event(action, phase, now);
+        }
+
+        if let Some(recorder) = &mut self.recorder {
+            recorder.end_frame(now);
+        }
+
+        if self
+            .replay
+            .as_ref()
+            .is_some_and(|replay| replay.frames.is_empty())
+        {
+            info!("input replay finished");
+            self.replay = None;
+        }
+
+        self.frame += 1;
+    }
+
+    /// Applies the inputs of the next replayed frame, with the clock set to their recorded times.
+    fn replay_frame(&mut self) {
+        let Some(frame) = self
+            .replay
+            .as_mut()
+            .and_then(|replay| replay.frames.pop_front())
+        else {
+            return;
+        };
+
+        for (time, input) in frame.inputs {
+            self.set_replay_time(time);
+            self.apply_raw(input);
+        }
+
+        self.set_replay_time(frame.time);
+    }
+
+    fn set_replay_time(&mut self, time: Duration) {
+        if let Some(replay) = &mut self.replay {
+            replay.time = time;
+        }
+    }
+
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+
+    /// Starts recording raw input. Held keys and mouse buttons, connected gamepads with their
+    /// state, the cursor position and the window size are recorded first, so that a replay
+    /// starts from the same state.
+    pub fn start_recording(&mut self) {
+        let now = self.now();
+        let mut recorder = Recorder::new(now);
+
+        recorder.record(now, RawInput::WindowSize(self.window_size));
+
+        if let Some(position) = self.cursor_position {
+            recorder.record(now, RawInput::CursorMoved(position));
+        }
+
+        for input in &self.held_inputs {
+            if !matches!(input, Input::Gamepad(_)) {
+                recorder.record(now, RawInput::Press(*input));
+            }
+        }
+
+        // Gamepad buttons are held through the state of their gamepads.
+        for gamepad in &self.gamepads {
+            let id = gamepad.id;
+            let mut record = |input| recorder.record(now, input);
+
+            record(RawInput::Gamepad(GamepadEvent::Connected {
+                id,
+                name: gamepad.name.clone(),
+            }));
+            record(RawInput::AssignPlayer {
+                id,
+                player: gamepad.player,
+            });
+
+            for &button in &gamepad.buttons {
+                record(RawInput::Gamepad(GamepadEvent::Button {
+                    id,
+                    button,
+                    pressed: true,
+                }));
+            }
+
+            for (&axis, &value) in &gamepad.axes {
+                record(RawInput::Gamepad(GamepadEvent::Axis { id, axis, value }));
+            }
+        }
+
+        self.recorder = Some(recorder);
+    }
+
+    /// Returns the frames recorded so far, input since the last `update` is dropped.
+    pub fn stop_recording(&mut self) -> Option<Recording> {
+        self.recorder.take().map(|recorder| recorder.recording)
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.recorder.is_some()
+    }
+
+    /// Replays a recording, one frame per `update`. Live input is ignored until the replay
+    /// finishes, held inputs are released so that they don't stick.
+    pub fn start_replay(&mut self, recording: Recording) {
+        let held: Vec<Input> = self.held_inputs.iter().copied().collect();
+
+        for input in held {
+            self.submit_input(input, ElementState::Released);
+        }
+
+        self.replay = Some(Replay::new(Instant::now(), recording));
+    }
+
+    pub fn stop_replay(&mut self) {
+        self.replay = None;
+    }
+
+    pub fn is_replaying(&self) -> bool {
+        self.replay.is_some()
+    }
+
+    /// Pushes a context on top of the others, replacing a context with the same name.
+    pub fn push_context(&mut self, context: InputContext) {
+        self.pop_context(context.name());
+        self.contexts.push(context);
+    }
+
+    pub fn pop_context(&mut self, name: &str) -> Option<InputContext> {
+        let index = self
+            .contexts
+            .iter()
+            .position(|context| context.name() == name)?;
+
+        Some(self.contexts.remove(index))
+    }
+
+    pub fn context(&self, name: &str) -> Option<&InputContext> {
+        self.contexts.iter().find(|context| context.name() == name)
+    }
+
+    pub fn context_mut(&mut self, name: &str) -> Option<&mut InputContext> {
+        self.contexts
+            .iter_mut()
+            .find(|context| context.name() == name)
+    }
+
+    pub fn contexts(&self) -> impl Iterator<Item = &InputContext> {
+        self.contexts.iter()
+    }
+
+    /// Enables text mode, in which key presses produce text events instead of actions. Held
+    /// keys other than modifiers are released so that their actions don't stay active while
+    /// typing.
+    pub fn set_text_mode(&mut self, enabled: bool) {
+        if self.text_mode == enabled {
+            return;
+        }
+
+        if enabled {
+            let held: Vec<Input> = self
+                .held_inputs
+                .iter()
+                .filter(
+                    |input| matches!(input, Input::Key(key) if Modifiers::of_key(*key).is_none()),
+                )
+                .copied()
+                .collect();
+
+            for input in held {
+                self.submit_input(input, ElementState::Released);
+            }
+        }
+
+        self.text_mode = enabled;
+        self.text_mode_changed = true;
+    }
+
+    pub fn is_text_mode(&self) -> bool {
+        self.text_mode
+    }
+
+    /// Text events since the last reset, in the order they happened.
+    pub fn text_events(&self) -> impl Iterator<Item = &TextEvent> {
+        self.text_events.iter()
+    }
+
+    pub(crate) fn take_text_mode_change(&mut self) -> Option<bool> {
+        std::mem::take(&mut self.text_mode_changed).then_some(self.text_mode)
+    }
+
+    /// Returns the actions triggered by pressing `input` with the currently held modifiers.
+    ///
+    /// A completed chord wins over combos of its last input. Otherwise contexts resolve the combo
+    /// from the highest priority down to the input map, until one consumes it. Within each, only
+    /// the combos requiring the most of the held modifiers trigger, so that Ctrl+S does not also
+    /// trigger S.
+    fn resolve_press(&mut self, input: Input) -> Vec<Action> {
+        let modifier = match input {
+            Input::Key(key) => Modifiers::of_key(key),
+            _ => None,
+        };
+
+        let modifiers = self.modifiers().without(modifier.unwrap_or_default());
+        let combo = Combo { modifiers, input };
+
+        // Modifiers are captured with the input pressed while holding them.
+        if matches!(self.capture, Capture::Waiting) {
+            if modifier.is_none() {
+                self.capture = Capture::Captured(combo);
+            }
+
+            return Vec::new();
+        }
+
+        // Modifier keys are part of the combos of a chord, pressing them doesn't advance it.
+        if modifier.is_none() {
+            let chord_actions = self.advance_chord(combo);
+
+            if !chord_actions.is_empty() {
+                return chord_actions;
+            }
+        }
+
+        let mut actions = Vec::new();
+        let mut consumed = false;
+
+        for (map, consumption) in self.press_layers() {
+            let layer_actions = resolve_combo(map, combo);
+
+            consumed = match consumption {
+                Consumption::None => false,
+                Consumption::Bound => !layer_actions.is_empty(),
+                Consumption::All => true,
+            };
+
+            actions.extend(layer_actions);
+
+            if consumed {
+                break;
+            }
+        }
+
+        if consumed {
+            self.consumed_inputs.insert(input);
+        }
+
+        actions
+    }
+
+    /// Maps of the enabled contexts in the order they resolve presses, ending with the input map
+    /// unless a context consumes everything.
+    fn layers(&self) -> Vec<(&InputMap, Consumption)> {
+        let mut contexts: Vec<&InputContext> = self
+            .contexts
+            .iter()
+            .rev()
+            .filter(|context| context.enabled)
+            .collect();
+        contexts.sort_by_key(|context| Reverse(context.priority));
+
+        let mut layers = Vec::new();
+
+        for context in contexts {
+            layers.push((&context.map, context.consumption));
+
+            if context.consumption == Consumption::All {
+                return layers;
+            }
+        }
+
+        layers.push((&self.map, Consumption::None));
+        layers
+    }
+
+    /// Layers that resolve presses, only the contexts in text mode.
+    fn press_layers(&self) -> Vec<(&InputMap, Consumption)> {
+        let mut layers = self.layers();
+
+        if self.text_mode {
+            layers.retain(|(map, _)| !std::ptr::eq(*map, &self.map));
+        }
+
+        layers
+    }
+
+    /// Adds a pressed combo to the chord in progress and returns the actions of completed chords.
+    fn advance_chord(&mut self, combo: Combo) -> Vec<Action> {
+        let now = self.now();
+
+        if now.duration_since(self.chord_updated) > self.chord_timeout {
+            self.chord_progress.clear();
+        }
+
+        self.chord_updated = now;
+
+        let mut progress = std::mem::take(&mut self.chord_progress);
+        progress.push(combo);
+
+        // Layers hide chords of the layers below them like they hide combos.
+        let mut chords: Vec<(&Vec<Combo>, &Action)> = Vec::new();
+
+        for (map, consumption) in self.press_layers() {
+            chords.extend(map.iter().filter_map(|(binding, action)| match binding {
+                Binding::Chord(chord) => Some((chord, action)),
+                Binding::Combo(_) => None,
+            }));
+
+            if consumption == Consumption::Bound && !resolve_combo(map, combo).is_empty() {
+                break;
+            }
+        }
+
+        let mut completed = Vec::new();
+
+        // Drop the oldest combos until the progress starts a bound chord again, so that
+        // pressing G, G, X still completes the chord G, X.
+        while !progress.is_empty() {
+            let mut is_prefix = false;
+
+            for (chord, action) in &chords {
+                if chord.as_slice() == progress.as_slice() {
+                    completed.push((*action).clone());
+                } else if chord.starts_with(&progress) {
+                    is_prefix = true;
+                }
+            }
+
+            if !completed.is_empty() {
+                progress.clear();
+                break;
+            }
+
+            if is_prefix {
+                break;
+            }
+
+            progress.remove(0);
+        }
+
+        self.chord_progress = progress;
+        completed
+    }
+
+    /// Whether pressing the input with the held modifiers triggers an action of a context that
+    /// is not hidden by a context above it.
+    fn is_bound_by_context(&self, input: Input) -> bool {
+        let combo = Combo {
+            modifiers: self.modifiers(),
+            input,
+        };
+
+        self.press_layers()
+            .into_iter()
+            .filter(|(map, _)| !std::ptr::eq(*map, &self.map))
+            .any(|(map, _)| !resolve_combo(map, combo).is_empty())
+    }
+
+    /// Whether a context hides an analog axis from the input map, by consuming everything or
+    /// binding the axis while consuming bound input.
+    fn is_analog_consumed(&self, axis: AnalogAxis) -> bool {
+        self.contexts
+            .iter()
+            .filter(|context| context.enabled)
+            .any(|context| match context.consumption {
+                Consumption::None => false,
+                Consumption::Bound => context.map.analog_axes().any(|bound| bound == axis),
+                Consumption::All => true,
+            })
+    }
+
+    /// Modifiers held right now.
+    pub fn modifiers(&self) -> Modifiers {
+        self.held_inputs
+            .iter()
+            .filter_map(|input| match input {
+                Input::Key(key) => Modifiers::of_key(*key),
+                _ => None,
+            })
+            .fold(Modifiers::NONE, BitOr::bitor)
+    }
+
+    pub fn submit_cursor_moved(&mut self, position: PhysicalPosition<f64>) {
+        self.submit_raw(RawInput::CursorMoved(Vec2::new(
+            position.x as f32,
+            position.y as f32,
+        )));
+    }
+
+    pub fn submit_cursor_left(&mut self) {
+        self.submit_raw(RawInput::CursorLeft);
+    }
+
+    pub fn submit_mouse_wheel(&mut self, delta: MouseScrollDelta) {
+        self.submit_raw(RawInput::MouseWheel(match delta {
+            MouseScrollDelta::LineDelta(x, y) => Vec2::new(x, y),
+            MouseScrollDelta::PixelDelta(position) => {
+                Vec2::new(position.x as f32, position.y as f32) / PIXELS_PER_LINE
+            }
+        }));
+    }
+
+    /// Submits raw device motion, which is not affected by cursor acceleration or the window edges.
+    pub fn submit_mouse_motion(&mut self, delta: (f64, f64)) {
+        self.submit_raw(RawInput::MouseMotion(Vec2::new(
+            delta.0 as f32,
+            delta.1 as f32,
+        )));
+    }
+
+    pub fn set_backend(&mut self, backend: Box<dyn InputBackend>) {
+        self.backend = Some(backend);
+    }
+
+    /// Applies events of the backend, call once per frame before `update`.
+    pub fn poll_backend(&mut self) {
+        let Some(backend) = &mut self.backend else {
+            return;
+        };
+
+        let mut events = Vec::new();
+        backend.poll(&mut events);
+
+        for event in events {
+            self.submit_gamepad_event(event);
+        }
+    }
+
+    pub fn submit_gamepad_event(&mut self, event: GamepadEvent) {
+        self.submit_raw(RawInput::Gamepad(event));
+    }
+
+    fn apply_gamepad_event(&mut self, event: GamepadEvent) {
+        match event {
+            GamepadEvent::Connected { id, name } => {
+                // A replay starts by connecting the gamepads that were connected when recording.
+                self.update_gamepad_buttons(|gamepads| {
+                    gamepads.retain(|gamepad| gamepad.id != id);
+                });
+
+                let player = (0..).find(|player| self.gamepad(*player).is_none());
+
+                info!("gamepad {} connected as player {:?}", name, player);
+
+                self.gamepads.push(Gamepad::new(id, name, player));
+            }
+            GamepadEvent::Disconnected { id } => {
+                self.update_gamepad_buttons(|gamepads| {
+                    gamepads.retain(|gamepad| gamepad.id != id);
+                });
+            }
+            GamepadEvent::Button {
+                id,
+                button,
+                pressed,
+            } => {
+                self.update_gamepad_buttons(|gamepads| {
+                    let Some(gamepad) = gamepads.iter_mut().find(|gamepad| gamepad.id == id) else {
+                        return;
+                    };
+
+                    if pressed {
+                        gamepad.buttons.insert(button);
+                    } else {
+                        gamepad.buttons.remove(&button);
+                    }
+                });
+            }
+            GamepadEvent::Axis { id, axis, value } => {
+                if let Some(gamepad) = self.gamepads.iter_mut().find(|gamepad| gamepad.id == id) {
+                    gamepad.axes.insert(axis, value);
+                }
+            }
+        }
+    }
+
+    /// Changes gamepads and submits presses and releases of buttons that became held or not
+    /// held by any gamepad assigned to a player.
+    fn update_gamepad_buttons(&mut self, update: impl FnOnce(&mut Vec<Gamepad>)) {
+        let held_before = self.held_gamepad_buttons();
+        update(&mut self.gamepads);
+        let held_after = self.held_gamepad_buttons();
+
+        for button in held_before.difference(&held_after) {
+            self.submit_input(Input::Gamepad(*button), ElementState::Released);
+        }
+
+        for button in held_after.difference(&held_before) {
+            self.submit_input(Input::Gamepad(*button), ElementState::Pressed);
+        }
+    }
+
+    fn held_gamepad_buttons(&self) -> HashSet<GamepadButton> {
+        self.gamepads
+            .iter()
+            .filter(|gamepad| gamepad.player.is_some())
+            .flat_map(|gamepad| gamepad.buttons.iter().copied())
+            .collect()
+    }
+
+    pub fn gamepads(&self) -> impl Iterator<Item = &Gamepad> {
+        self.gamepads.iter()
+    }
+
+    /// The gamepad assigned to a player.
+    pub fn gamepad(&self, player: usize) -> Option<&Gamepad> {
+        self.gamepads
+            .iter()
+            .find(|gamepad| gamepad.player == Some(player))
+    }
+
+    /// Assigns a gamepad to a player, the gamepad previously assigned to the player becomes
+    /// unassigned. Connected gamepads are assigned to the first free player.
+    pub fn assign_player(&mut self, id: GamepadId, player: Option<usize>) {
+        self.submit_raw(RawInput::AssignPlayer { id, player });
+    }
+
+    fn apply_player(&mut self, id: GamepadId, player: Option<usize>) {
+        self.update_gamepad_buttons(|gamepads| {
+            for gamepad in gamepads.iter_mut() {
+                if gamepad.id == id {
+                    gamepad.player = player;
+                } else if player.is_some() && gamepad.player == player {
+                    gamepad.player = None;
+                }
+            }
+        });
+    }
+
+    /// Requests vibration of the gamepad assigned to a player.
+    pub fn rumble(&mut self, player: usize, rumble: Rumble) {
+        let Some(id) = self.gamepad(player).map(|gamepad| gamepad.id) else {
+            return;
+        };
+
+        if let Some(backend) = &mut self.backend {
+            backend.rumble(id, rumble);
+        }
+    }
+
+    pub fn set_window_size(&mut self, size: PhysicalSize<u32>) {
+        self.submit_raw(RawInput::WindowSize(Vec2::new(
+            size.width as f32,
+            size.height as f32,
+        )));
+    }
+
+    /// Action events since the last reset, in the order they happened.
+    pub fn action_events(&self) -> impl Iterator<Item = &ActionEvent> {
         self.action_queue.iter()
     }
 
     pub fn reset(&mut self) {
         self.action_queue.clear();
+        self.just_pressed.clear();
+        self.just_released.clear();
+        self.text_events.clear();
+        self.scroll_delta = Vec2::ZERO;
+        self.mouse_motion = Vec2::ZERO;
     }
 
     pub fn is_action_active(&self, action: &Action) -> bool {
-        self.active_actions.contains(action)
+        self.active_actions.contains_key(action)
+    }
+
+    /// Whether an input of the action was pressed since the last reset while none were held.
+    pub fn is_action_just_pressed(&self, action: &Action) -> bool {
+        self.just_pressed.contains(action)
+    }
+
+    /// Whether the last held input of the action was released since the last reset.
+    pub fn is_action_just_released(&self, action: &Action) -> bool {
+        self.just_released.contains(action)
+    }
+
+    pub fn just_pressed_actions(&self) -> impl Iterator<Item = &Action> {
+        self.just_pressed.iter()
+    }
+
+    pub fn just_released_actions(&self) -> impl Iterator<Item = &Action> {
+        self.just_released.iter()
+    }
+
+    /// Whether the interaction of the action was performed since the last reset.
+    pub fn is_action_performed(&self, action: &Action) -> bool {
+        self.action_queue
+            .iter()
+            .any(|event| event.action == *action && event.phase == ActionPhase::Performed)
+    }
+
+    /// Value of a one dimensional axis action, 0 if the action has no axis bindings.
+    pub fn axis(&self, action: &Action) -> f32 {
+        self.axis_2d(action).x
+    }
+
+    /// Value of a two dimensional axis action, zero if the action has no axis bindings.
+    pub fn axis_2d(&self, action: &Action) -> Vec2 {
+        let mut consumed: Vec<AnalogAxis> = Vec::new();
+        let mut value = Vec2::ZERO;
+
+        for (map, consumption) in self.layers() {
+            for binding in map.axes(action) {
+                value += binding.value(
+                    |input| {
+                        self.held_inputs.contains(&input) && !self.consumed_inputs.contains(&input)
+                    },
+                    |axis| {
+                        if consumed.contains(&axis) {
+                            0.0
+                        } else {
+                            self.analog_value(axis)
+                        }
+                    },
+                );
+            }
+
+            // Analog axes bound by a layer are hidden from the layers below it.
+            if consumption == Consumption::Bound {
+                consumed.extend(map.analog_axes());
+            }
+        }
+
+        value
+    }
+
+    fn analog_value(&self, axis: AnalogAxis) -> f32 {
+        match axis {
+            AnalogAxis::MouseX => self.mouse_motion.x,
+            AnalogAxis::MouseY => self.mouse_motion.y,
+            AnalogAxis::WheelX => self.scroll_delta.x,
+            AnalogAxis::WheelY => self.scroll_delta.y,
+            // The strongest deflection of all gamepads assigned to a player.
+            AnalogAxis::Gamepad(axis) => self
+                .gamepads
+                .iter()
+                .filter(|gamepad| gamepad.player.is_some())
+                .map(|gamepad| gamepad.axis(axis))
+                .fold(0.0, |strongest, value| {
+                    if value.abs() > strongest.abs() {
+                        value
+                    } else {
+                        strongest
+                    }
+                }),
+        }
+    }
+
+    /// Cursor position in physical pixels from the top left corner of the window, `None` if the
+    /// cursor is outside the window.
+    pub fn cursor_position(&self) -> Option<Vec2> {
+        self.cursor_position
+    }
+
+    /// Cursor position scaled to `0..1` over the window.
+    pub fn cursor_position_normalized(&self) -> Option<Vec2> {
+        if self.window_size.cmple(Vec2::ZERO).any() {
+            return None;
+        }
+
+        self.cursor_position
+            .map(|position| position / self.window_size)
+    }
+
+    /// Scroll distance in lines since the last reset, positive `y` scrolls up. Zero along axes
+    /// that a context consumes, which reads them through its axis actions instead.
+    pub fn scroll_delta(&self) -> Vec2 {
+        self.unconsumed(self.scroll_delta, AnalogAxis::WheelX, AnalogAxis::WheelY)
+    }
+
+    /// Raw mouse motion since the last reset, zero along axes that a context consumes.
+    pub fn mouse_motion(&self) -> Vec2 {
+        self.unconsumed(self.mouse_motion, AnalogAxis::MouseX, AnalogAxis::MouseY)
+    }
+
+    fn unconsumed(&self, value: Vec2, x: AnalogAxis, y: AnalogAxis) -> Vec2 {
+        let visible = |axis, value| {
+            if self.is_analog_consumed(axis) {
+                0.0
+            } else {
+                value
+            }
+        };
+
+        Vec2::new(visible(x, value.x), visible(y, value.y))
+    }
+
+    pub fn cursor_mode(&self) -> CursorMode {
+        self.cursor_mode
+    }
+
+    pub fn set_cursor_mode(&mut self, mode: CursorMode) {
+        if self.cursor_mode != mode {
+            self.cursor_mode = mode;
+            self.cursor_mode_changed = true;
+        }
+    }
+
+    /// Returns the cursor mode if it changed since the last call, to be applied to the window.
+    pub(crate) fn take_cursor_mode_change(&mut self) -> Option<CursorMode> {
+        std::mem::take(&mut self.cursor_mode_changed).then_some(self.cursor_mode)
+    }
+}
+
+enum Capture {
+    Off,
+    Waiting,
+    Captured(Combo),
+}
+
+/// Actions of the combos of `map` that the pressed combo triggers, only the combos requiring the
+/// most of its modifiers.
+fn resolve_combo(map: &InputMap, combo: Combo) -> Vec<Action> {
+    let triggers =
+        |bound: &Combo| bound.input == combo.input && combo.modifiers.contains(bound.modifiers);
+
+    let specificity = map
+        .iter()
+        .filter_map(|(binding, _)| match binding {
+            Binding::Combo(bound) if triggers(bound) => Some(bound.modifiers.count()),
+            _ => None,
+        })
+        .max();
+
+    let Some(specificity) = specificity else {
+        return Vec::new();
+    };
+
+    map.iter()
+        .filter(|(binding, _)| match binding {
+            Binding::Combo(bound) => triggers(bound) && bound.modifiers.count() == specificity,
+            _ => false,
+        })
+        .map(|(_, action)| action.clone())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread::sleep;
+
+    use super::*;
+    use crate::vfs::{Permissions, VirtualFs};
+
+    /// Action and text events of a frame.
+    type FrameEvents = (Vec<(Action, ActionPhase)>, Vec<TextEvent>);
+
+    /// Runs one frame per step, returning the events of each frame.
+    fn run(input: &mut InputHandler, steps: &[&dyn Fn(&mut InputHandler)]) -> Vec<FrameEvents> {
+        steps
+            .iter()
+            .map(|step| {
+                step(input);
+                input.update();
+
+                let actions = input
+                    .action_events()
+                    .map(|event| (event.action.clone(), event.phase))
+                    .collect();
+                let text = input.text_events().cloned().collect();
+
+                input.reset();
+                (actions, text)
+            })
+            .collect()
+    }
+
+    fn bound_handler() -> InputHandler {
+        let mut input = InputHandler::new();
+        input.add_action(Input::Mouse(MouseButton::Left), Action::new("fire"));
+        input.add_action(Input::Gamepad(GamepadButton::South), Action::new("jump"));
+        input.set_interaction(
+            Action::new("jump"),
+            Interaction::Tap(Duration::from_secs(60)),
+        );
+        input
+    }
+
+    /// Polls the gamepads, updates the handler and returns the phases of an action this frame.
+    fn frame(input: &mut InputHandler, action: &Action) -> Vec<ActionPhase> {
+        input.poll_backend();
+        input.update();
+
+        let phases = input
+            .action_events()
+            .filter(|event| event.action == *action)
+            .map(|event| event.phase)
+            .collect();
+
+        input.reset();
+        phases
+    }
+
+    #[test]
+    fn mouse_buttons_cursor_and_motion() {
+        let mut input = bound_handler();
+        let fire = Action::new("fire");
+
+        input.set_window_size(PhysicalSize::new(200, 100));
+        input.submit_cursor_moved(PhysicalPosition::new(50.0, 25.0));
+        input.submit_mouse_button(MouseButton::Left, ElementState::Pressed);
+        input.submit_mouse_wheel(MouseScrollDelta::LineDelta(0.0, 1.0));
+        input.submit_mouse_wheel(MouseScrollDelta::PixelDelta(PhysicalPosition::new(
+            0.0,
+            f64::from(PIXELS_PER_LINE),
+        )));
+        input.submit_mouse_motion((3.0, -4.0));
+        input.submit_mouse_motion((1.0, 0.0));
+        input.update();
+
+        assert!(input.is_action_just_pressed(&fire));
+        assert_eq!(input.cursor_position(), Some(Vec2::new(50.0, 25.0)));
+        assert_eq!(
+            input.cursor_position_normalized(),
+            Some(Vec2::new(0.25, 0.25))
+        );
+        assert_eq!(input.scroll_delta(), Vec2::new(0.0, 2.0));
+        assert_eq!(input.mouse_motion(), Vec2::new(4.0, -4.0));
+        input.reset();
+
+        input.submit_cursor_left();
+        input.submit_mouse_button(MouseButton::Left, ElementState::Released);
+        input.update();
+
+        assert!(input.is_action_just_released(&fire));
+        assert!(!input.is_action_active(&fire));
+        assert_eq!(input.cursor_position(), None);
+        assert_eq!(input.scroll_delta(), Vec2::ZERO);
+        assert_eq!(input.mouse_motion(), Vec2::ZERO);
+    }
+
+    #[test]
+    fn cursor_mode_changes_are_taken_once() {
+        let mut input = InputHandler::new();
+        assert_eq!(input.cursor_mode(), CursorMode::Normal);
+
+        input.set_cursor_mode(CursorMode::Normal);
+        assert_eq!(input.take_cursor_mode_change(), None);
+
+        input.set_cursor_mode(CursorMode::Confined);
+        input.set_cursor_mode(CursorMode::Locked);
+        assert_eq!(input.take_cursor_mode_change(), Some(CursorMode::Locked));
+        assert_eq!(input.take_cursor_mode_change(), None);
+        assert_eq!(input.cursor_mode(), CursorMode::Locked);
+    }
+
+    #[test]
+    fn combos_trigger_the_most_specific_bindings() {
+        let key = |input: &mut InputHandler, key, state| input.submit_key(key, state, None);
+
+        let mut input = InputHandler::new();
+        input.add_action(Input::Key(KeyCode::KeyS), Action::new("back"));
+        input.add_action(Input::Key(KeyCode::ArrowDown), Action::new("back"));
+        input.add_action(Input::Key(KeyCode::KeyS), Action::new("crouch"));
+        input.add_action("Ctrl+KeyS".parse::<Binding>().unwrap(), Action::new("save"));
+
+        // One input triggers every action bound to it.
+        key(&mut input, KeyCode::KeyS, ElementState::Pressed);
+        assert!(input.is_action_just_pressed(&Action::new("back")));
+        assert!(input.is_action_just_pressed(&Action::new("crouch")));
+        assert!(!input.is_action_just_pressed(&Action::new("save")));
+
+        // The action stays active until its last held input is released.
+        key(&mut input, KeyCode::ArrowDown, ElementState::Pressed);
+        key(&mut input, KeyCode::KeyS, ElementState::Released);
+        assert!(input.is_action_active(&Action::new("back")));
+        assert!(!input.is_action_active(&Action::new("crouch")));
+        key(&mut input, KeyCode::ArrowDown, ElementState::Released);
+        assert!(input.is_action_just_released(&Action::new("back")));
+        input.reset();
+
+        key(&mut input, KeyCode::ControlLeft, ElementState::Pressed);
+        key(&mut input, KeyCode::KeyS, ElementState::Pressed);
+        assert_eq!(input.modifiers(), Modifiers::CTRL);
+        assert!(input.is_action_just_pressed(&Action::new("save")));
+        assert!(!input.is_action_just_pressed(&Action::new("back")));
+        assert!(!input.is_action_just_pressed(&Action::new("crouch")));
+    }
+
+    #[test]
+    fn chords_need_their_combos_in_order_and_in_time() {
+        let press = |input: &mut InputHandler, key| {
+            input.submit_key(key, ElementState::Pressed, None);
+            input.submit_key(key, ElementState::Released, None);
+        };
+
+        let chord = Action::new("chord");
+        let mut input = InputHandler::new();
+        input.add_action(
+            "Ctrl+KeyK Ctrl+KeyC".parse::<Binding>().unwrap(),
+            chord.clone(),
+        );
+        input.set_chord_timeout(Duration::from_millis(50));
+
+        input.submit_key(KeyCode::ControlLeft, ElementState::Pressed, None);
+        press(&mut input, KeyCode::KeyC);
+        press(&mut input, KeyCode::KeyK);
+        assert!(!input.is_action_just_pressed(&chord));
+
+        press(&mut input, KeyCode::KeyC);
+        assert!(input.is_action_just_pressed(&chord));
+        input.reset();
+
+        // Another combo in between restarts the chord.
+        press(&mut input, KeyCode::KeyK);
+        press(&mut input, KeyCode::KeyX);
+        press(&mut input, KeyCode::KeyC);
+        assert!(!input.is_action_just_pressed(&chord));
+
+        press(&mut input, KeyCode::KeyK);
+        sleep(Duration::from_millis(100));
+        press(&mut input, KeyCode::KeyC);
+        assert!(!input.is_action_just_pressed(&chord));
+    }
+
+    #[test]
+    fn captured_combos_rebind_actions() {
+        let key = |input: &mut InputHandler, key, state| input.submit_key(key, state, None);
+        let reload = Action::new("reload");
+
+        let mut input = InputHandler::new();
+        input.add_action(Input::Key(KeyCode::KeyR), reload.clone());
+        input.add_action(
+            "Ctrl+KeyR".parse::<Binding>().unwrap(),
+            Action::new("restart"),
+        );
+
+        input.capture_next_combo();
+        key(&mut input, KeyCode::ControlLeft, ElementState::Pressed);
+        assert!(input.is_capturing());
+        key(&mut input, KeyCode::KeyR, ElementState::Pressed);
+        assert!(!input.is_capturing());
+        assert_eq!(input.action_events().count(), 0);
+
+        let combo = input.take_captured_combo().unwrap();
+        assert_eq!(combo, Combo::new(Modifiers::CTRL, KeyCode::KeyR));
+        assert_eq!(input.take_captured_combo(), None);
+
+        key(&mut input, KeyCode::KeyR, ElementState::Released);
+        key(&mut input, KeyCode::ControlLeft, ElementState::Released);
+        input.rebind(&reload, [Binding::Combo(combo), KeyCode::F5.into()]);
+        assert_eq!(input.bindings(&reload).count(), 2);
+
+        key(&mut input, KeyCode::KeyR, ElementState::Pressed);
+        assert!(!input.is_action_just_pressed(&reload));
+        key(&mut input, KeyCode::F5, ElementState::Pressed);
+        assert!(input.is_action_just_pressed(&reload));
+
+        let root = std::env::temp_dir().join(format!("nechto-rebind-{}", std::process::id()));
+        let mut vfs = VirtualFs::new();
+        vfs.add_search_path_with_permissions("$user", root.clone(), Permissions::READ_WRITE);
+
+        input.input_map().save(&vfs, "$user/input.ini").unwrap();
+        let loaded = InputMap::load(&vfs, "$user/input.ini").unwrap();
+        assert_eq!(&loaded, input.input_map());
+        std::fs::remove_dir_all(root).unwrap();
+
+        input.capture_next_combo();
+        input.cancel_capture();
+        key(&mut input, KeyCode::KeyX, ElementState::Pressed);
+        assert_eq!(input.take_captured_combo(), None);
+    }
+
+    #[test]
+    fn gamepads_are_assigned_to_players() {
+        let gamepads = VirtualGamepads::new();
+        let mut input = bound_handler();
+        input.set_backend(Box::new(gamepads.clone()));
+        let jump = Action::new("jump");
+
+        let first = gamepads.connect("first");
+        let second = gamepads.connect("second");
+        input.poll_backend();
+        assert_eq!(input.gamepad(0).unwrap().id, first);
+        assert_eq!(input.gamepad(1).unwrap().id, second);
+
+        // Only gamepads assigned to a player press buttons.
+        input.assign_player(second, None);
+        gamepads.press(second, GamepadButton::South);
+        gamepads.set_axis(second, GamepadAxis::LeftStickX, 1.0);
+        input.poll_backend();
+        assert!(!input.is_action_active(&jump));
+        assert!(
+            input
+                .gamepads()
+                .any(|gamepad| gamepad.is_pressed(GamepadButton::South))
+        );
+
+        // Taking the player of another gamepad unassigns it.
+        input.assign_player(second, Some(0));
+        assert!(input.is_action_active(&jump));
+        assert_eq!(input.gamepad(0).unwrap().id, second);
+        assert!(
+            input
+                .gamepads()
+                .any(|gamepad| gamepad.id == first && gamepad.player.is_none())
+        );
+
+        let rumble = Rumble {
+            strong: 1.0,
+            weak: 0.5,
+            duration: Duration::from_millis(100),
+        };
+        input.rumble(0, rumble);
+        input.rumble(1, rumble);
+        assert_eq!(gamepads.take_rumbles(), [(second, rumble)]);
+
+        // Disconnecting releases the buttons of the gamepad.
+        gamepads.disconnect(second);
+        input.poll_backend();
+        assert!(!input.is_action_active(&jump));
+        assert!(input.gamepad(0).is_none());
+        assert_eq!(input.gamepads().count(), 1);
+    }
+
+    #[test]
+    fn interaction_phases() {
+        use ActionPhase::*;
+
+        let gamepads = VirtualGamepads::new();
+        let mut input = InputHandler::new();
+        input.set_backend(Box::new(gamepads.clone()));
+
+        let pad = gamepads.connect("virtual");
+        let south = GamepadButton::South;
+
+        let cases = [
+            (
+                Interaction::Press,
+                vec![Started, Performed],
+                vec![Cancelled],
+            ),
+            (
+                Interaction::Tap(Duration::from_secs(60)),
+                vec![Started],
+                vec![Performed],
+            ),
+            (
+                Interaction::Hold(Duration::from_secs(60)),
+                vec![Started],
+                vec![Cancelled],
+            ),
+        ];
+
+        for (interaction, pressed, released) in cases {
+            let action = Action::new("jump");
+            input.set_input_map(InputMap::new());
+            input.add_action(Input::Gamepad(south), action.clone());
+            input.set_interaction(action.clone(), interaction);
+
+            gamepads.press(pad, south);
+            assert_eq!(frame(&mut input, &action), pressed, "{interaction:?}");
+            assert!(input.is_action_active(&action));
+
+            gamepads.release(pad, south);
+            assert_eq!(frame(&mut input, &action), released, "{interaction:?}");
+            assert!(!input.is_action_active(&action));
+        }
+
+        // Holds perform and taps cancel once their duration passes while held.
+        let hold = Action::new("charge");
+        input.add_action(Input::Gamepad(GamepadButton::East), hold.clone());
+        input.set_interaction(hold.clone(), Interaction::Hold(Duration::from_millis(20)));
+
+        gamepads.press(pad, GamepadButton::East);
+        assert_eq!(frame(&mut input, &hold), [Started]);
+        sleep(Duration::from_millis(30));
+        assert_eq!(frame(&mut input, &hold), [Performed]);
+        assert_eq!(frame(&mut input, &hold), []);
+        gamepads.release(pad, GamepadButton::East);
+        assert_eq!(frame(&mut input, &hold), [Cancelled]);
+
+        let tap = Action::new("dodge");
+        input.add_action(Input::Gamepad(GamepadButton::West), tap.clone());
+        input.set_interaction(tap.clone(), Interaction::Tap(Duration::from_millis(20)));
+
+        gamepads.press(pad, GamepadButton::West);
+        assert_eq!(frame(&mut input, &tap), [Started]);
+        sleep(Duration::from_millis(30));
+        assert_eq!(frame(&mut input, &tap), [Cancelled]);
+        gamepads.release(pad, GamepadButton::West);
+        assert_eq!(frame(&mut input, &tap), []);
+
+        // A second press within the duration performs a double tap, a late one starts over.
+        let double_tap = Action::new("dash");
+        input.add_action(Input::Gamepad(GamepadButton::North), double_tap.clone());
+        input.set_interaction(
+            double_tap.clone(),
+            Interaction::DoubleTap(Duration::from_secs(60)),
+        );
+
+        for phases in [[Started], [Performed], [Started]] {
+            gamepads.press(pad, GamepadButton::North);
+            gamepads.release(pad, GamepadButton::North);
+            assert_eq!(frame(&mut input, &double_tap), phases);
+        }
+    }
+
+    #[test]
+    fn action_events_keep_their_order_and_time() {
+        use ActionPhase::*;
+
+        let fire = Action::new("fire");
+        let jump = Action::new("jump");
+        let mut input = bound_handler();
+        input.set_interaction(fire.clone(), Interaction::Tap(Duration::from_secs(60)));
+
+        let before = Instant::now();
+        input.submit_mouse_button(MouseButton::Left, ElementState::Pressed);
+        sleep(Duration::from_millis(5));
+        input.submit_gamepad_event(GamepadEvent::Connected {
+            id: GamepadId(1),
+            name: "pad".to_string(),
+        });
+        input.assign_player(GamepadId(1), Some(0));
+        input.submit_gamepad_event(GamepadEvent::Button {
+            id: GamepadId(1),
+            button: GamepadButton::South,
+            pressed: true,
+        });
+        sleep(Duration::from_millis(5));
+        input.submit_mouse_button(MouseButton::Left, ElementState::Released);
+        input.update();
+
+        let events: Vec<_> = input
+            .action_events()
+            .map(|event| (event.action.clone(), event.phase, event.time))
+            .collect();
+        let phases: Vec<_> = events
+            .iter()
+            .map(|(action, phase, _)| (action.clone(), *phase))
+            .collect();
+
+        assert_eq!(
+            phases,
+            [
+                (fire.clone(), Started),
+                (jump.clone(), Started),
+                (fire.clone(), Performed),
+            ]
+        );
+        assert!(events[0].2 >= before);
+        assert!(events.windows(2).all(|pair| pair[0].2 < pair[1].2));
+        assert!(input.is_action_performed(&fire));
+        assert!(!input.is_action_performed(&jump));
+
+        input.reset();
+        assert_eq!(input.action_events().count(), 0);
+    }
+
+    #[test]
+    fn replays_recorded_input() {
+        let gamepad = GamepadId(0);
+        let button = |pressed| GamepadEvent::Button {
+            id: gamepad,
+            button: GamepadButton::South,
+            pressed,
+        };
+
+        let steps: [&dyn Fn(&mut InputHandler); 6] = [
+            &|input| {
+                input.submit_gamepad_event(GamepadEvent::Connected {
+                    id: gamepad,
+                    name: "virtual pad".into(),
+                })
+            },
+            &|input| {
+                input.submit_mouse_button(MouseButton::Left, ElementState::Pressed);
+                input.submit_gamepad_event(button(true));
+            },
+            &|input| {
+                input.submit_mouse_button(MouseButton::Left, ElementState::Released);
+                input.submit_gamepad_event(button(false));
+                input.set_text_mode(true);
+            },
+            &|input| {
+                input.submit_ime(Ime::Preedit(" ni\\".into(), Some((1, 3))));
+                input.submit_ime(Ime::Preedit(String::new(), None));
+            },
+            &|input| input.submit_ime(Ime::Commit("two words\n".into())),
+            &|input| {
+                input.set_text_mode(false);
+                input.submit_mouse_button(MouseButton::Left, ElementState::Pressed);
+            },
+        ];
+
+        let mut recorder = bound_handler();
+        recorder.start_recording();
+        let recorded = run(&mut recorder, &steps);
+        let recording = recorder.stop_recording().unwrap();
+
+        // Saved recordings keep whole microseconds.
+        let recording = Recording::parse(&recording.to_string()).unwrap();
+        assert_eq!(Recording::parse(&recording.to_string()).unwrap(), recording);
+
+        // Live input during the replay is ignored, only text mode is set by the caller.
+        let mut replayer = bound_handler();
+        replayer.start_replay(recording);
+        let replayed = run(&mut replayer, &steps);
+
+        assert!(!replayer.is_replaying());
+        assert_eq!(replayed, recorded);
+        assert!(recorded.iter().any(|(actions, _)| !actions.is_empty()));
+        assert_eq!(recorded[4].1, [TextEvent::Text("two words\n".into())]);
+    }
+
+    #[test]
+    fn contexts_consume_chords_and_analog_axes() {
+        let press = |input: &mut InputHandler, button| {
+            input.submit_mouse_button(button, ElementState::Pressed);
+            input.submit_mouse_button(button, ElementState::Released);
+        };
+
+        let chord = Action::new("chord");
+        let mut input = InputHandler::new();
+        input.add_action(
+            "MouseBack MouseForward".parse::<Binding>().unwrap(),
+            chord.clone(),
+        );
+        input.add_axis(
+            Action::new("look"),
+            AxisBinding::new(AxisSource::mouse_motion()),
+        );
+
+        let mut ui = InputMap::new();
+        ui.add(Input::Mouse(MouseButton::Back), Action::new("back"));
+        ui.add_axis(
+            AxisBinding::new(AxisSource::mouse_motion()),
+            Action::new("pan"),
+        );
+        input.push_context(InputContext::new("ui", 1).with_map(ui));
+
+        // The context binds the first combo of the chord, so the input map never sees it.
+        press(&mut input, MouseButton::Back);
+        press(&mut input, MouseButton::Forward);
+        assert!(input.is_action_just_pressed(&Action::new("back")));
+        assert!(!input.is_action_just_pressed(&chord));
+
+        input.submit_mouse_motion((3.0, 4.0));
+        assert_eq!(input.axis_2d(&Action::new("pan")), Vec2::new(3.0, 4.0));
+        assert_eq!(input.axis_2d(&Action::new("look")), Vec2::ZERO);
+        assert_eq!(input.mouse_motion(), Vec2::ZERO);
+
+        input.context_mut("ui").unwrap().enabled = false;
+        assert_eq!(input.axis_2d(&Action::new("look")), Vec2::new(3.0, 4.0));
+        assert_eq!(input.mouse_motion(), Vec2::new(3.0, 4.0));
+
+        press(&mut input, MouseButton::Back);
+        press(&mut input, MouseButton::Forward);
+        assert!(input.is_action_just_pressed(&chord));
+    }
+
+    #[test]
+    fn context_combos_with_modifiers_fire_in_text_mode() {
+        let submit = Action::new("submit");
+        let undo = Action::new("undo");
+        let sprint = Action::new("sprint");
+
+        let mut input = InputHandler::new();
+        input.add_action(Input::Key(KeyCode::ShiftLeft), sprint.clone());
+        input.add_action(
+            "Ctrl+Enter".parse::<Binding>().unwrap(),
+            Action::new("base"),
+        );
+
+        let mut console = InputMap::new();
+        console.add("Ctrl+Enter".parse::<Binding>().unwrap(), submit.clone());
+        input.push_context(InputContext::new("console", 1).with_map(console));
+
+        let mut editor = InputMap::new();
+        editor.add("Ctrl+KeyZ".parse::<Binding>().unwrap(), undo.clone());
+        input.push_context(InputContext::new("editor", 0).with_map(editor));
+
+        // Held before typing starts, the modifier stays held.
+        input.submit_key(KeyCode::ControlLeft, ElementState::Pressed, None);
+        input.set_text_mode(true);
+
+        input.submit_key(KeyCode::Enter, ElementState::Pressed, Some("\r"));
+        input.submit_key(KeyCode::Enter, ElementState::Released, None);
+        input.submit_key(KeyCode::ControlLeft, ElementState::Released, None);
+        assert!(input.is_action_just_pressed(&submit));
+        assert!(!input.is_action_just_pressed(&Action::new("base")));
+        input.reset();
+
+        // Modifiers pressed while typing are held and also reach the text field.
+        input.submit_key(KeyCode::ShiftLeft, ElementState::Pressed, None);
+        input.submit_key(KeyCode::ControlLeft, ElementState::Pressed, None);
+        input.submit_key(KeyCode::KeyZ, ElementState::Pressed, Some("\u{1a}"));
+        assert!(input.is_action_just_pressed(&undo));
+        assert!(!input.is_action_active(&sprint));
+
+        input.submit_key(KeyCode::KeyZ, ElementState::Released, None);
+        input.submit_key(KeyCode::ControlLeft, ElementState::Released, None);
+        input.submit_key(KeyCode::KeyA, ElementState::Pressed, Some("A"));
+
+        let text: Vec<TextEvent> = input.text_events().cloned().collect();
+        assert_eq!(
+            text,
+            [
+                TextEvent::Key(KeyCode::ShiftLeft),
+                TextEvent::Key(KeyCode::ControlLeft),
+                TextEvent::Key(KeyCode::KeyA),
+                TextEvent::Text("A".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn context_stack_resolves_modified_combos_in_text_mode() {
+        let hidden = Action::new("hidden");
+        let modal = Action::new("modal");
+
+        let mut input = InputHandler::new();
+
+        let mut editor = InputMap::new();
+        editor.add("Ctrl+KeyS".parse::<Binding>().unwrap(), hidden.clone());
+        editor.add("Ctrl+KeyO".parse::<Binding>().unwrap(), hidden.clone());
+        input.push_context(InputContext::new("editor", 0).with_map(editor));
+
+        let mut dialog = InputMap::new();
+        dialog.add("Ctrl+KeyS".parse::<Binding>().unwrap(), modal.clone());
+        input.push_context(
+            InputContext::new("dialog", 1)
+                .with_consumption(Consumption::All)
+                .with_map(dialog),
+        );
+
+        input.set_text_mode(true);
+        input.submit_key(KeyCode::ControlLeft, ElementState::Pressed, None);
+
+        // The modal dialog hides the editor, so Ctrl+O is typed instead.
+        input.submit_key(KeyCode::KeyS, ElementState::Pressed, None);
+        input.submit_key(KeyCode::KeyO, ElementState::Pressed, None);
+        assert!(input.is_action_just_pressed(&modal));
+        assert!(!input.is_action_just_pressed(&hidden));
+        assert!(
+            input
+                .text_events()
+                .any(|event| *event == TextEvent::Key(KeyCode::KeyO))
+        );
+
+        input.submit_key(KeyCode::KeyS, ElementState::Released, None);
+        input.submit_key(KeyCode::KeyO, ElementState::Released, None);
+        input.reset();
+
+        input.context_mut("dialog").unwrap().enabled = false;
+        input.submit_key(KeyCode::KeyO, ElementState::Pressed, None);
+        assert!(input.is_action_just_pressed(&hidden));
+        assert_eq!(input.text_events().count(), 0);
+    }
+
+    #[test]
+    fn recordings_start_from_the_gamepad_state() {
+        let gamepads = VirtualGamepads::new();
+        let mut recorder = bound_handler();
+        recorder.set_backend(Box::new(gamepads.clone()));
+
+        let pad = gamepads.connect("pad \\ with\nnewline ");
+        gamepads.press(pad, GamepadButton::South);
+        gamepads.set_axis(pad, GamepadAxis::LeftStickX, 0.5);
+        recorder.poll_backend();
+        recorder.assign_player(pad, Some(2));
+        recorder.update();
+        recorder.reset();
+
+        let step = |input: &mut InputHandler| input.poll_backend();
+        let steps: [&dyn Fn(&mut InputHandler); 2] = [&step, &step];
+
+        recorder.start_recording();
+        gamepads.release(pad, GamepadButton::South);
+        let recorded = run(&mut recorder, &steps);
+        let recording = recorder.stop_recording().unwrap();
+
+        let text = recording.to_string();
+        assert!(
+            text.lines().all(|line| line.starts_with(['0', '1'])),
+            "{text}"
+        );
+        let recording = Recording::parse(&text).unwrap();
+
+        let mut replayer = bound_handler();
+        replayer.start_replay(recording);
+        let replayed = run(&mut replayer, &steps);
+
+        // The held button is pressed again when the replay starts, so the tap starts over and
+        // completes like in the recording.
+        let jump = |phase| (Action::new("jump"), phase);
+        assert_eq!(recorded[0].0, [jump(ActionPhase::Performed)]);
+        assert_eq!(
+            replayed[0].0,
+            [jump(ActionPhase::Started), jump(ActionPhase::Performed)]
+        );
+        assert_eq!(replayed[1..], recorded[1..]);
+
+        let gamepad = replayer.gamepad(2).unwrap();
+        assert_eq!(gamepad.name, "pad \\ with\nnewline ");
+        assert_eq!(gamepad.axis(GamepadAxis::LeftStickX), 0.5);
     }
 }
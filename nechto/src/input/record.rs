@@ -0,0 +1,332 @@
+use std::collections::VecDeque;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use glam::Vec2;
+
+use crate::input::binding::{parse_gamepad_axis, parse_gamepad_button, parse_key};
+use crate::input::{GamepadEvent, GamepadId, Input, TextEvent};
+use crate::vfs::{self, IntoPathSpec, VirtualFs};
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("line {line}: invalid recorded input")]
+    InvalidLine { line: usize },
+
+    #[error("recording is not valid UTF-8")]
+    InvalidUtf8,
+
+    #[error(transparent)]
+    Vfs(#[from] vfs::Error),
+}
+
+/// Input as submitted to `InputHandler`, before it is resolved to actions.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RawInput {
+    Press(Input),
+    Release(Input),
+    CursorMoved(Vec2),
+    CursorLeft,
+    /// Scroll in lines.
+    MouseWheel(Vec2),
+    MouseMotion(Vec2),
+    WindowSize(Vec2),
+    Gamepad(GamepadEvent),
+    /// `InputHandler::assign_player`.
+    AssignPlayer {
+        id: GamepadId,
+        player: Option<usize>,
+    },
+    Text(TextEvent),
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RecordedFrame {
+    /// Time of the `update` that ended the frame, since the recording started.
+    pub time: Duration,
+    pub inputs: Vec<(Duration, RawInput)>,
+}
+
+/// Raw input of consecutive frames, stored as lines of `frame microseconds input`, where the
+/// last line of each frame is its `update`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Recording {
+    pub frames: Vec<RecordedFrame>,
+}
+
+impl Recording {
+    pub fn new() -> Self {
+        Self { frames: Vec::new() }
+    }
+
+    pub fn parse(text: &str) -> Result<Self, Error> {
+        let mut recording = Recording::new();
+        let mut frame = RecordedFrame::default();
+
+        for (index, line) in text.lines().enumerate() {
+            let line = line.trim();
+
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let invalid = || Error::InvalidLine { line: index + 1 };
+
+            let mut words = line.splitn(3, ' ');
+
+            let number: usize = parse_word(words.next()).ok_or_else(invalid)?;
+            let time = Duration::from_micros(parse_word(words.next()).ok_or_else(invalid)?);
+            let input = words.next().ok_or_else(invalid)?;
+
+            if number != recording.frames.len() {
+                return Err(invalid());
+            }
+
+            if input == "update" {
+                frame.time = time;
+                recording.frames.push(std::mem::take(&mut frame));
+            } else {
+                let input = parse_raw_input(input).ok_or_else(invalid)?;
+                frame.inputs.push((time, input));
+            }
+        }
+
+        // Inputs after the last update never reached a frame.
+        Ok(recording)
+    }
+
+    pub fn load(vfs: &VirtualFs, path: impl IntoPathSpec) -> Result<Self, Error> {
+        let data = vfs.read(path)?;
+        let text = String::from_utf8(data).map_err(|_| Error::InvalidUtf8)?;
+
+        Self::parse(&text)
+    }
+
+    pub fn save(&self, vfs: &VirtualFs, path: impl IntoPathSpec) -> Result<(), Error> {
+        vfs.write(path, self.to_string())?;
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for Recording {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (number, frame) in self.frames.iter().enumerate() {
+            for (time, input) in &frame.inputs {
+                write!(f, "{} {} ", number, time.as_micros())?;
+                write_raw_input(f, input)?;
+                writeln!(f)?;
+            }
+
+            writeln!(f, "{} {} update", number, frame.time.as_micros())?;
+        }
+
+        Ok(())
+    }
+}
+
+fn write_raw_input(f: &mut fmt::Formatter<'_>, input: &RawInput) -> fmt::Result {
+    match input {
+        RawInput::Press(input) => write!(f, "press {}", input),
+        RawInput::Release(input) => write!(f, "release {}", input),
+        RawInput::CursorMoved(position) => write!(f, "cursor {} {}", position.x, position.y),
+        RawInput::CursorLeft => write!(f, "cursor_left"),
+        RawInput::MouseWheel(delta) => write!(f, "wheel {} {}", delta.x, delta.y),
+        RawInput::MouseMotion(delta) => write!(f, "motion {} {}", delta.x, delta.y),
+        RawInput::WindowSize(size) => write!(f, "window {} {}", size.x, size.y),
+        RawInput::Gamepad(event) => match event {
+            GamepadEvent::Connected { id, name } => {
+                write!(f, "gamepad_connected {} {}", id.0, escape(name))
+            }
+            GamepadEvent::Disconnected { id } => write!(f, "gamepad_disconnected {}", id.0),
+            GamepadEvent::Button {
+                id,
+                button,
+                pressed: true,
+            } => write!(f, "gamepad_press {} {:?}", id.0, button),
+            GamepadEvent::Button {
+                id,
+                button,
+                pressed: false,
+            } => write!(f, "gamepad_release {} {:?}", id.0, button),
+            GamepadEvent::Axis { id, axis, value } => {
+                write!(f, "gamepad_axis {} {:?} {}", id.0, axis, value)
+            }
+        },
+        RawInput::AssignPlayer {
+            id,
+            player: Some(player),
+        } => write!(f, "gamepad_player {} {}", id.0, player),
+        RawInput::AssignPlayer { id, player: None } => write!(f, "gamepad_player {} none", id.0),
+        RawInput::Text(event) => match event {
+            TextEvent::Text(text) => write!(f, "text {}", escape(text)),
+            TextEvent::Preedit {
+                text,
+                cursor: Some((start, end)),
+            } => write!(f, "preedit {} {} {}", start, end, escape(text)),
+            TextEvent::Preedit { text, cursor: None } => {
+                write!(f, "preedit none {}", escape(text))
+            }
+            TextEvent::Key(key) => write!(f, "text_key {:?}", key),
+        },
+    }
+}
+
+fn parse_raw_input(text: &str) -> Option<RawInput> {
+    let (kind, args) = text.split_once(' ').unwrap_or((text, ""));
+    let mut words = args.split(' ');
+
+    let vec2 = |words: &mut std::str::Split<'_, char>| {
+        Some(Vec2::new(
+            parse_word(words.next())?,
+            parse_word(words.next())?,
+        ))
+    };
+
+    let input = match kind {
+        "press" => RawInput::Press(args.parse().ok()?),
+        "release" => RawInput::Release(args.parse().ok()?),
+        "cursor" => RawInput::CursorMoved(vec2(&mut words)?),
+        "cursor_left" => RawInput::CursorLeft,
+        "wheel" => RawInput::MouseWheel(vec2(&mut words)?),
+        "motion" => RawInput::MouseMotion(vec2(&mut words)?),
+        "window" => RawInput::WindowSize(vec2(&mut words)?),
+        "gamepad_connected" => {
+            let (id, name) = args.split_once(' ').unwrap_or((args, ""));
+
+            RawInput::Gamepad(GamepadEvent::Connected {
+                id: GamepadId(id.parse().ok()?),
+                name: unescape(name)?,
+            })
+        }
+        "gamepad_player" => RawInput::AssignPlayer {
+            id: GamepadId(parse_word(words.next())?),
+            player: match words.next()? {
+                "none" => None,
+                player => Some(player.parse().ok()?),
+            },
+        },
+        "gamepad_disconnected" => RawInput::Gamepad(GamepadEvent::Disconnected {
+            id: GamepadId(parse_word(words.next())?),
+        }),
+        "gamepad_press" | "gamepad_release" => RawInput::Gamepad(GamepadEvent::Button {
+            id: GamepadId(parse_word(words.next())?),
+            button: parse_gamepad_button(words.next()?)?,
+            pressed: kind == "gamepad_press",
+        }),
+        "gamepad_axis" => RawInput::Gamepad(GamepadEvent::Axis {
+            id: GamepadId(parse_word(words.next())?),
+            axis: parse_gamepad_axis(words.next()?)?,
+            value: parse_word(words.next())?,
+        }),
+        "text" => RawInput::Text(TextEvent::Text(unescape(args)?)),
+        "preedit" => {
+            let cursor = match words.next()? {
+                "none" => None,
+                start => Some((start.parse().ok()?, parse_word(words.next())?)),
+            };
+
+            RawInput::Text(TextEvent::Preedit {
+                text: unescape(words.next().unwrap_or(""))?,
+                cursor,
+            })
+        }
+        "text_key" => RawInput::Text(TextEvent::Key(parse_key(args)?)),
+        _ => return None,
+    };
+
+    Some(input)
+}
+
+/// Escapes whitespace and backslashes, so that text is one word that survives trimming.
+fn escape(text: &str) -> String {
+    let mut escaped = String::new();
+
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            ' ' => escaped.push_str("\\s"),
+            '\t' => escaped.push_str("\\t"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+fn unescape(text: &str) -> Option<String> {
+    let mut unescaped = String::new();
+    let mut chars = text.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+
+        unescaped.push(match chars.next()? {
+            '\\' => '\\',
+            's' => ' ',
+            't' => '\t',
+            'n' => '\n',
+            'r' => '\r',
+            _ => return None,
+        });
+    }
+
+    Some(unescaped)
+}
+
+fn parse_word<T: std::str::FromStr>(word: Option<&str>) -> Option<T> {
+    word?.parse().ok()
+}
+
+pub(crate) struct Recorder {
+    pub started: Instant,
+    pub recording: Recording,
+    pub frame: RecordedFrame,
+}
+
+impl Recorder {
+    pub fn new(started: Instant) -> Self {
+        Self {
+            started,
+            recording: Recording::new(),
+            frame: RecordedFrame::default(),
+        }
+    }
+
+    pub fn record(&mut self, time: Instant, input: RawInput) {
+        self.frame
+            .inputs
+            .push((time.duration_since(self.started), input));
+    }
+
+    pub fn end_frame(&mut self, time: Instant) {
+        self.frame.time = time.duration_since(self.started);
+        self.recording.frames.push(std::mem::take(&mut self.frame));
+    }
+}
+
+/// Frames of a recording not replayed yet, with the clock set to the recorded times.
+pub(crate) struct Replay {
+    pub started: Instant,
+    pub time: Duration,
+    pub frames: VecDeque<RecordedFrame>,
+}
+
+impl Replay {
+    pub fn new(started: Instant, recording: Recording) -> Self {
+        Self {
+            started,
+            time: Duration::ZERO,
+            frames: recording.frames.into(),
+        }
+    }
+
+    pub fn now(&self) -> Instant {
+        self.started + self.time
+    }
+}
@@ -0,0 +1,16 @@
+use winit::keyboard::KeyCode;
+
+/// Input received while text mode is enabled, separate from actions.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TextEvent {
+    /// Typed characters or text committed by the IME.
+    Text(String),
+    /// Text being composed in the IME, empty when composition ends. The cursor is a byte range
+    /// in the text, if the IME shows one.
+    Preedit {
+        text: String,
+        cursor: Option<(usize, usize)>,
+    },
+    /// A pressed or repeating key, for editing keys like `Backspace`, `Enter` or arrows.
+    Key(KeyCode),
+}
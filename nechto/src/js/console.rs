@@ -1,8 +1,9 @@
-use rquickjs::function::IntoJsFunc;
 use rquickjs::prelude::Rest;
-use rquickjs::{Ctx, Function, Object, Value};
+use rquickjs::{Ctx, Object, Value};
 use tracing::info;
 
+use crate::js::set_function;
+
 pub fn console_object(ctx: Ctx) -> Object {
     let object = Object::new(ctx.clone()).unwrap();
 
@@ -11,18 +12,6 @@ pub fn console_object(ctx: Ctx) -> Object {
     object
 }
 
-fn set_function<'a, P, F>(ctx: Ctx<'a>, object: &Object<'a>, name: &str, func: F)
-where
-    F: IntoJsFunc<'a, P> + 'a,
-{
-    object
-        .set(
-            name,
-            Function::new(ctx, func).unwrap().with_name(name).unwrap(),
-        )
-        .unwrap();
-}
-
 fn log(rest: Rest<Value>) {
     for obj in rest.0 {
         info!("{:?}", obj);
@@ -0,0 +1,165 @@
+use std::sync::Arc;
+
+use tracing::warn;
+
+use crate::event::{Payload, RawEvent};
+use crate::js::{Context, ContextOptions, Error, Message};
+use crate::vfs::VirtualFs;
+
+/// Isolated script contexts addressed by name, with message routing between them.
+pub struct Contexts {
+    vfs: Arc<VirtualFs>,
+    contexts: Vec<Context>,
+    messages: Vec<Message>,
+}
+
+impl Contexts {
+    pub fn new(vfs: Arc<VirtualFs>) -> Self {
+        Self {
+            vfs,
+            contexts: Vec::new(),
+            messages: Vec::new(),
+        }
+    }
+
+    /// Creates a context and evaluates its entry module. Names of contexts are unique.
+    pub fn create(&mut self, options: ContextOptions) -> Result<&mut Context, Error> {
+        if self.get(&options.name).is_some() {
+            return Err(Error::DuplicateContext(options.name));
+        }
+
+        self.contexts
+            .push(Context::new(Arc::clone(&self.vfs), options)?);
+        Ok(self.contexts.last_mut().unwrap())
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<Context> {
+        let index = self
+            .contexts
+            .iter()
+            .position(|context| context.name() == name)?;
+
+        Some(self.contexts.remove(index))
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Context> {
+        self.contexts.iter().find(|context| context.name() == name)
+    }
+
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut Context> {
+        self.contexts
+            .iter_mut()
+            .find(|context| context.name() == name)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Context> {
+        self.contexts.iter()
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut Context> {
+        self.contexts.iter_mut()
+    }
+
+    /// Queues a message for the context named `to`, delivered on the next `update`.
+    pub fn post_message(
+        &mut self,
+        from: impl Into<String>,
+        to: impl Into<String>,
+        payload: Payload,
+    ) {
+        self.messages.push(Message {
+            from: from.into(),
+            to: to.into(),
+            payload,
+        });
+    }
+
+    pub fn dispatch_events<'a, I>(&mut self, events: I)
+    where
+        I: IntoIterator<Item = &'a RawEvent>,
+        I::IntoIter: Clone,
+    {
+        let events = events.into_iter();
+
+        for context in &mut self.contexts {
+            context.dispatch_events(events.clone());
+        }
+    }
+
+    pub fn take_emitted_events(&mut self) -> Vec<RawEvent> {
+        self.contexts
+            .iter_mut()
+            .flat_map(|context| context.take_emitted_events())
+            .collect()
+    }
+
+    /// Delivers queued messages and updates every context.
+    ///
+    /// Messages posted by scripts during the update are delivered on the next one.
+    pub fn update(&mut self) {
+        for context in &mut self.contexts {
+            self.messages.extend(context.take_posted_messages());
+        }
+
+        for message in std::mem::take(&mut self.messages) {
+            match self.get_mut(&message.to) {
+                Some(context) => context.deliver_message(&message),
+                None => warn!(
+                    "dropped message from {} to unknown script context {}",
+                    message.from, message.to
+                ),
+            }
+        }
+
+        for context in &mut self.contexts {
+            context.update();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn failed_and_duplicate_contexts_are_errors() {
+        let root = std::env::temp_dir().join(format!("nechto-contexts-{}", std::process::id()));
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(root.join("init.js"), "export const ready = true;").unwrap();
+        std::fs::write(root.join("throws.js"), "throw new Error('broken mod');").unwrap();
+        std::fs::write(root.join("invalid.js"), "export const = ;").unwrap();
+
+        let mut vfs = VirtualFs::new();
+        vfs.add_search_path("$script", root.clone());
+
+        let mut contexts = Contexts::new(Arc::new(vfs));
+        let options = |name: &str, entry: &str| ContextOptions {
+            name: name.to_string(),
+            root: "$script".to_string(),
+            entry: entry.to_string(),
+            ..Default::default()
+        };
+
+        assert!(contexts.create(options("main", "init")).is_ok());
+        assert!(matches!(
+            contexts.create(options("main", "init")),
+            Err(Error::DuplicateContext(_))
+        ));
+
+        for entry in ["throws", "invalid", "missing"] {
+            let result = contexts.create(options(entry, entry));
+            assert!(
+                matches!(&result, Err(Error::Exception { context, .. }) if context == entry),
+                "{entry}"
+            );
+        }
+
+        match contexts.create(options("mod", "throws")) {
+            Err(err) => assert!(err.to_string().contains("broken mod"), "{err}"),
+            Ok(_) => panic!("the entry module throws"),
+        }
+
+        assert_eq!(contexts.iter().count(), 1);
+        std::fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -0,0 +1,335 @@
+/// TypeScript declarations of everything `js::Context` registers, one file per binding module.
+///
+/// These are the `.d.ts` files in `src/js/types`, the same files
+/// `nechto_build::build_type_declarations` concatenates into `nechto.d.ts`.
+pub const TYPE_DECLARATIONS: &[&str] = include!(concat!(env!("OUT_DIR"), "/type_declarations.rs"));
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeSet, HashSet};
+    use std::sync::Arc;
+
+    use rquickjs::{CatchResultExt, Ctx, Function, Module, Object, Value};
+
+    use super::*;
+    use crate::js::{Context, ContextOptions, NativeModule};
+    use crate::vfs::VirtualFs;
+
+    /// Describes a value and its members as `path` for values and `path(arity)` for functions.
+    ///
+    /// Class constructors list their static members as `Class.name` and their prototype members as
+    /// `Class.prototype.name`. Accessors are described as values.
+    const DESCRIBE: &str = r#"
+        (path, value) => {
+            const described = [];
+
+            const members = (path, object, skipped) => {
+                for (const name of Object.getOwnPropertyNames(object)) {
+                    if (skipped.includes(name)) {
+                        continue;
+                    }
+
+                    const descriptor = Object.getOwnPropertyDescriptor(object, name);
+
+                    if (descriptor.get || descriptor.set) {
+                        described.push(`${path}.${name}`);
+                    } else {
+                        describe(`${path}.${name}`, descriptor.value);
+                    }
+                }
+            };
+
+            const describe = (path, value) => {
+                if (typeof value === "function") {
+                    described.push(`${path}(${value.length})`);
+                    members(path, value, ["length", "name", "prototype"]);
+
+                    if (value.prototype) {
+                        members(`${path}.prototype`, value.prototype, ["constructor"]);
+                    }
+                } else {
+                    described.push(path);
+
+                    if (typeof value === "object" && value !== null) {
+                        members(path, value, []);
+                    }
+                }
+            };
+
+            describe(path, value);
+
+            return described;
+        }
+    "#;
+
+    /// Number of parameters a call has to pass to a declared function, `params` being the text
+    /// between its parentheses.
+    fn declared_arity(params: &str) -> usize {
+        let mut depth = 0;
+        let mut required = 0;
+        let mut param = String::new();
+
+        for c in params.chars().chain([',']) {
+            match c {
+                '(' | '[' | '{' => depth += 1,
+                ')' | ']' | '}' => depth -= 1,
+                ',' if depth == 0 => {
+                    let name = param.split(':').next().unwrap().trim();
+
+                    if !name.is_empty() && !name.ends_with('?') && !name.starts_with("...") {
+                        required += 1;
+                    }
+
+                    param.clear();
+                    continue;
+                }
+                _ => {}
+            }
+
+            param.push(c);
+        }
+
+        required
+    }
+
+    /// Splits a declared member like `static fromArray(value: Vec2Like): Vec2;` into its name
+    /// and, for functions, its arity.
+    fn declared_member(declaration: &str) -> Option<(&str, Option<usize>)> {
+        let end = declaration.find(['(', ':', ';', '<', '=', ' '])?;
+        let name = &declaration[..end];
+
+        if name.is_empty() {
+            return None;
+        }
+
+        let arity = declaration[end..].strip_prefix('(').map(|rest| {
+            let mut depth = 1;
+            let close = rest
+                .find(|c| {
+                    match c {
+                        '(' => depth += 1,
+                        ')' => depth -= 1,
+                        _ => {}
+                    }
+                    depth == 0
+                })
+                .unwrap();
+
+            declared_arity(&rest[..close])
+        });
+
+        Some((name, arity))
+    }
+
+    fn described(path: String, arity: Option<usize>) -> String {
+        match arity {
+            Some(arity) => format!("{}({})", path, arity),
+            None => path,
+        }
+    }
+
+    /// Declaration whose members are being read, with the path of its binding.
+    enum Scope {
+        Class(String),
+        Object(String),
+        Interface,
+    }
+
+    /// Bindings declared by `TYPE_DECLARATIONS`, described like `DESCRIBE` does. Globals are
+    /// named `name` and module exports `module/name`.
+    fn declared_bindings() -> BTreeSet<String> {
+        let mut bindings = BTreeSet::new();
+
+        for source in TYPE_DECLARATIONS {
+            let mut module = None;
+            let mut scope = None;
+
+            for line in source.lines() {
+                let line = line.trim();
+
+                if let Some(rest) = line.strip_prefix("declare module ") {
+                    module = rest.split('"').nth(1);
+                    continue;
+                }
+
+                if line.starts_with('}') {
+                    match scope {
+                        Some(_) => scope = None,
+                        None => module = None,
+                    }
+                    continue;
+                }
+
+                if let Some(scope) = &scope {
+                    let (is_static, member) = match line.strip_prefix("static ") {
+                        Some(member) => (true, member),
+                        None => (false, line),
+                    };
+
+                    let Some((name, arity)) = declared_member(member) else {
+                        continue;
+                    };
+
+                    let path = match (scope, name, is_static) {
+                        (Scope::Interface, ..) => continue,
+                        (Scope::Class(class), "constructor", _) => {
+                            bindings.remove(&format!("{}(0)", class));
+                            bindings.insert(described(class.clone(), arity));
+                            continue;
+                        }
+                        (Scope::Class(class), _, false) => format!("{}.prototype.{}", class, name),
+                        (Scope::Class(path) | Scope::Object(path), ..) => {
+                            format!("{}.{}", path, name)
+                        }
+                    };
+
+                    bindings.insert(described(path, arity));
+                    continue;
+                }
+
+                let declaration = match module {
+                    Some(_) => line.strip_prefix("export "),
+                    None => line.strip_prefix("declare "),
+                };
+
+                let Some((kind, declaration)) = declaration.and_then(|line| line.split_once(' '))
+                else {
+                    continue;
+                };
+
+                let Some((name, arity)) = declared_member(declaration) else {
+                    continue;
+                };
+
+                let path = match module {
+                    Some(module) => format!("{}/{}", module, name),
+                    None => name.to_string(),
+                };
+
+                match kind {
+                    "function" => {
+                        bindings.insert(described(path, arity));
+                    }
+                    "class" => {
+                        bindings.insert(described(path.clone(), Some(0)));
+                        scope = Some(Scope::Class(path));
+                    }
+                    "const" | "let" => {
+                        if line.ends_with('{') {
+                            scope = Some(Scope::Object(path.clone()));
+                        }
+                        bindings.insert(path);
+                    }
+                    "interface" => scope = Some(Scope::Interface),
+                    _ => {}
+                }
+            }
+        }
+
+        bindings
+    }
+
+    /// Bindings registered in a context, described by `DESCRIBE`.
+    ///
+    /// `globals` are the names registered on the global object and `modules` the specifiers of
+    /// native modules, whose exports are read from their namespace objects.
+    fn registered_bindings(
+        ctx: &Ctx,
+        globals: &[String],
+        modules: &[&str],
+    ) -> rquickjs::Result<BTreeSet<String>> {
+        let describe: Function = ctx.eval(DESCRIBE)?;
+        let mut bindings = BTreeSet::new();
+
+        for name in globals {
+            let value: Value = ctx.globals().get(name.as_str())?;
+            bindings.extend(describe.call::<_, Vec<String>>((name.as_str(), value))?);
+        }
+
+        for module in modules {
+            let namespace: Object = Module::import(ctx, *module)?.finish()?;
+
+            for export in namespace.keys::<String>() {
+                let export = export?;
+                let value: Value = namespace.get(export.as_str())?;
+                let path = format!("{}/{}", module, export);
+                bindings.extend(describe.call::<_, Vec<String>>((path, value))?);
+            }
+        }
+
+        Ok(bindings)
+    }
+
+    #[test]
+    fn declared_arity_counts_required_parameters() {
+        assert_eq!(declared_arity(""), 0);
+        assert_eq!(declared_arity("x?: number, y?: number"), 0);
+        assert_eq!(declared_arity("...values: unknown[]"), 0);
+        assert_eq!(declared_arity("name: string, payload?: Payload"), 1);
+        assert_eq!(
+            declared_arity("name: string, callback: (payload: any, other: any) => void"),
+            2
+        );
+    }
+
+    #[test]
+    fn declarations_match_bindings() {
+        let root = std::env::temp_dir().join(format!("nechto-declarations-{}", std::process::id()));
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(root.join("init.js"), "").unwrap();
+
+        let mut vfs = VirtualFs::new();
+        vfs.add_search_path("$script", root.clone());
+
+        let context = Context::new(
+            Arc::new(vfs),
+            ContextOptions {
+                root: "$script".to_string(),
+                native_modules: NativeModule::ALL.to_vec(),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        // Globals of a context without engine bindings.
+        let builtin_globals: HashSet<String> = {
+            let runtime = rquickjs::Runtime::new().unwrap();
+            let ctx = rquickjs::Context::full(&runtime).unwrap();
+            ctx.with(|ctx| {
+                ctx.globals()
+                    .keys::<String>()
+                    .collect::<Result<_, _>>()
+                    .unwrap()
+            })
+        };
+
+        let modules: Vec<&str> = NativeModule::ALL
+            .iter()
+            .map(NativeModule::specifier)
+            .collect();
+
+        let registered = context.ctx.with(|ctx| {
+            let globals: Vec<String> = ctx
+                .globals()
+                .keys::<String>()
+                .map(Result::unwrap)
+                .filter(|name| !builtin_globals.contains(name))
+                .collect();
+
+            registered_bindings(&ctx, &globals, &modules)
+                .catch(&ctx)
+                .unwrap()
+        });
+
+        std::fs::remove_dir_all(root).unwrap();
+
+        let declared = declared_bindings();
+
+        assert!(
+            registered == declared,
+            "undeclared bindings: {:?}\ndeclarations without bindings: {:?}",
+            registered.difference(&declared).collect::<Vec<_>>(),
+            declared.difference(&registered).collect::<Vec<_>>()
+        );
+    }
+}
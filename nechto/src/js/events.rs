@@ -0,0 +1,292 @@
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+
+use rquickjs::function::Opt;
+use rquickjs::module::{Declarations, Exports, ModuleDef};
+use rquickjs::{Ctx, FromJs, Function, IntoJs, JsLifetime, Object, Value};
+use tracing::error;
+
+use crate::event::{Payload, RawEvent};
+use crate::js::{NativeModule, export_function, profiler};
+
+/// Script listeners and events emitted by scripts, stored as context userdata.
+pub(crate) struct ScriptEvents<'js> {
+    listeners: RefCell<HashMap<String, Vec<Function<'js>>>>,
+    emitted: RefCell<Vec<RawEvent>>,
+}
+
+unsafe impl<'js> JsLifetime<'js> for ScriptEvents<'js> {
+    type Changed<'to> = ScriptEvents<'to>;
+}
+
+impl<'js> ScriptEvents<'js> {
+    pub fn new() -> Self {
+        Self {
+            listeners: RefCell::new(HashMap::new()),
+            emitted: RefCell::new(Vec::new()),
+        }
+    }
+}
+
+pub struct EventsModule;
+
+impl ModuleDef for EventsModule {
+    fn declare(decl: &Declarations) -> rquickjs::Result<()> {
+        decl.declare("on")?;
+        decl.declare("off")?;
+        decl.declare("emit")?;
+
+        Ok(())
+    }
+
+    fn evaluate<'js>(ctx: &Ctx<'js>, exports: &Exports<'js>) -> rquickjs::Result<()> {
+        export_function(
+            ctx,
+            exports,
+            NativeModule::Events,
+            "on",
+            Function::new(ctx.clone(), on)?,
+        )?;
+        export_function(
+            ctx,
+            exports,
+            NativeModule::Events,
+            "off",
+            Function::new(ctx.clone(), off)?,
+        )?;
+        export_function(
+            ctx,
+            exports,
+            NativeModule::Events,
+            "emit",
+            Function::new(ctx.clone(), emit)?,
+        )?;
+
+        Ok(())
+    }
+}
+
+fn on<'js>(ctx: Ctx<'js>, name: String, callback: Function<'js>) {
+    let events = ctx.userdata::<ScriptEvents>().unwrap();
+    events
+        .listeners
+        .borrow_mut()
+        .entry(name)
+        .or_default()
+        .push(callback);
+}
+
+fn off<'js>(ctx: Ctx<'js>, name: String, callback: Function<'js>) {
+    let events = ctx.userdata::<ScriptEvents>().unwrap();
+    if let Some(listeners) = events.listeners.borrow_mut().get_mut(&name) {
+        listeners.retain(|listener| *listener != callback);
+    }
+}
+
+fn emit<'js>(ctx: Ctx<'js>, name: String, payload: Opt<Payload>) {
+    let events = ctx.userdata::<ScriptEvents>().unwrap();
+    events.emitted.borrow_mut().push(RawEvent {
+        name,
+        payload: payload.0.unwrap_or_default(),
+    });
+}
+
+pub(crate) fn take_emitted(ctx: &Ctx) -> Vec<RawEvent> {
+    let events = ctx.userdata::<ScriptEvents>().unwrap();
+    events.emitted.take()
+}
+
+pub(crate) fn dispatch(ctx: &Ctx, event: &RawEvent) {
+    // Copy the listeners so that callbacks can subscribe and unsubscribe while being called.
+    let listeners = {
+        let events = ctx.userdata::<ScriptEvents>().unwrap();
+        let listeners = events.listeners.borrow();
+        listeners.get(&event.name).cloned().unwrap_or_default()
+    };
+
+    for listener in listeners {
+        let _scope = profiler::scope(ctx, || format!("event {}", event.name));
+
+        let result = event
+            .payload
+            .clone()
+            .into_js(ctx)
+            .and_then(|payload| listener.call::<_, ()>((payload,)));
+
+        if let Err(err) = result {
+            let exception = ctx.catch();
+            error!(
+                "script listener for {} failed: {} {:?}",
+                event.name, err, exception
+            );
+        }
+    }
+}
+
+impl<'js> IntoJs<'js> for Payload {
+    fn into_js(self, ctx: &Ctx<'js>) -> rquickjs::Result<Value<'js>> {
+        match self {
+            Payload::Null => Ok(Value::new_null(ctx.clone())),
+            Payload::Bool(value) => value.into_js(ctx),
+            Payload::Number(value) => value.into_js(ctx),
+            Payload::String(value) => value.into_js(ctx),
+            Payload::Array(values) => values.into_js(ctx),
+            Payload::Object(fields) => {
+                let object = Object::new(ctx.clone())?;
+
+                for (key, value) in fields {
+                    object.set(key, value)?;
+                }
+
+                Ok(object.into_value())
+            }
+        }
+    }
+}
+
+/// Nesting depth of arrays and objects converted to a `Payload`, deeper values are rejected so
+/// that cyclic values don't overflow the stack.
+const MAX_PAYLOAD_DEPTH: usize = 64;
+
+impl<'js> FromJs<'js> for Payload {
+    fn from_js(_ctx: &Ctx<'js>, value: Value<'js>) -> rquickjs::Result<Self> {
+        payload_from_js(value, 0)
+    }
+}
+
+fn payload_from_js(value: Value<'_>, depth: usize) -> rquickjs::Result<Payload> {
+    if let Some(value) = value.as_bool() {
+        return Ok(Payload::Bool(value));
+    }
+
+    if let Some(value) = value.as_number() {
+        return Ok(Payload::Number(value));
+    }
+
+    if let Some(value) = value.as_string() {
+        return Ok(Payload::String(value.to_string()?));
+    }
+
+    if value.is_function() {
+        return Err(rquickjs::Error::new_from_js("function", "Payload"));
+    }
+
+    if (value.is_array() || value.is_object()) && depth >= MAX_PAYLOAD_DEPTH {
+        return Err(rquickjs::Error::new_from_js_message(
+            value.type_name(),
+            "Payload",
+            format!("nested deeper than {} levels or cyclic", MAX_PAYLOAD_DEPTH),
+        ));
+    }
+
+    if let Some(array) = value.as_array() {
+        return array
+            .iter::<Value>()
+            .map(|value| payload_from_js(value?, depth + 1))
+            .collect::<rquickjs::Result<_>>()
+            .map(Payload::Array);
+    }
+
+    if let Some(object) = value.as_object() {
+        let mut fields = BTreeMap::new();
+
+        for entry in object.props::<String, Value>() {
+            let (key, value) = entry?;
+            fields.insert(key, payload_from_js(value, depth + 1)?);
+        }
+
+        return Ok(Payload::Object(fields));
+    }
+
+    Ok(Payload::Null)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::js::{Context, ContextOptions};
+    use crate::vfs::VirtualFs;
+
+    #[test]
+    fn cyclic_payloads_are_rejected() {
+        let runtime = rquickjs::Runtime::new().unwrap();
+        let ctx = rquickjs::Context::full(&runtime).unwrap();
+
+        ctx.with(|ctx| {
+            let nested: Payload = ctx.eval("({ a: [1, { b: 'c' }] })").unwrap();
+            assert_eq!(
+                nested,
+                Payload::object([(
+                    "a",
+                    Payload::Array(vec![
+                        Payload::Number(1.0),
+                        Payload::object([("b", Payload::String("c".to_string()))]),
+                    ])
+                )])
+            );
+
+            let cyclic: rquickjs::Result<Payload> = ctx.eval("const a = {}; a.self = a; a");
+            assert!(cyclic.is_err());
+
+            let cyclic: rquickjs::Result<Payload> = ctx.eval("const b = []; b.push(b); b");
+            assert!(cyclic.is_err());
+        });
+    }
+
+    #[test]
+    fn scripts_listen_to_and_emit_events() {
+        let root = std::env::temp_dir().join(format!("nechto-events-{}", std::process::id()));
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(
+            root.join("init.js"),
+            "import { on, off, emit } from 'nechto:events';
+            const echo = (payload) => emit('echo', { action: payload.action, count: 1 });
+            on('action.pressed', echo);
+            on('action.released', (payload) => off('action.pressed', echo));
+            emit('ready');",
+        )
+        .unwrap();
+
+        let mut vfs = VirtualFs::new();
+        vfs.add_search_path("$script", root.clone());
+
+        let mut context = Context::new(
+            Arc::new(vfs),
+            ContextOptions {
+                root: "$script".to_string(),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        std::fs::remove_dir_all(root).unwrap();
+
+        let emitted = context.take_emitted_events();
+        assert_eq!(emitted.len(), 1);
+        assert_eq!(emitted[0].name, "ready");
+        assert_eq!(emitted[0].payload, Payload::Null);
+
+        let pressed = RawEvent {
+            name: "action.pressed".to_string(),
+            payload: Payload::object([("action", "jump".into())]),
+        };
+        let released = RawEvent {
+            name: "action.released".to_string(),
+            payload: Payload::object([("action", "jump".into())]),
+        };
+
+        context.dispatch_events([&pressed]);
+        let emitted = context.take_emitted_events();
+        assert_eq!(emitted.len(), 1);
+        assert_eq!(emitted[0].name, "echo");
+        assert_eq!(
+            emitted[0].payload,
+            Payload::object([("action", "jump".into()), ("count", 1.into())])
+        );
+
+        context.dispatch_events([&released, &pressed]);
+        assert!(context.take_emitted_events().is_empty());
+    }
+}
@@ -0,0 +1,288 @@
+use std::path::{Component, Path};
+use std::sync::Arc;
+
+use rquickjs::module::{Declarations, Exports, ModuleDef};
+use rquickjs::{ArrayBuffer, Ctx, Exception, Function, IntoJs, JsLifetime, Object, Value};
+
+use crate::js::{NativeModule, export_function, task};
+use crate::vfs::{self, DirEntry, VirtualFs};
+
+/// The filesystem scripts are allowed to access, stored as context userdata.
+#[derive(Clone)]
+pub(crate) struct ScriptVfs {
+    pub vfs: Arc<VirtualFs>,
+    /// Path specs of the directories of `ContextOptions::fs_roots`.
+    pub roots: Vec<String>,
+}
+
+impl ScriptVfs {
+    /// Checks that a path is in one of the directories of the context before it is accessed.
+    fn check(&self, path: &str) -> Result<(), vfs::Error> {
+        let (prefix, relative) = path.split_once('/').unwrap_or((path, ""));
+        let relative = Path::new(relative);
+
+        let is_normal = relative
+            .components()
+            .all(|component| matches!(component, Component::Normal(_) | Component::CurDir));
+
+        let is_in_root = self.roots.iter().any(|root| {
+            let (root_prefix, root_relative) = root.split_once('/').unwrap_or((root, ""));
+            prefix == root_prefix && relative.starts_with(root_relative)
+        });
+
+        if is_normal && is_in_root {
+            Ok(())
+        } else {
+            Err(vfs::Error::PermissionDenied {
+                path_spec: path.to_string(),
+                access: "read",
+            })
+        }
+    }
+}
+
+unsafe impl<'js> JsLifetime<'js> for ScriptVfs {
+    type Changed<'to> = ScriptVfs;
+}
+
+pub struct FsModule;
+
+const SYNC_FUNCTIONS: &[&str] = &["readText", "readBytes", "readJson", "exists", "readDir"];
+
+const ASYNC_FUNCTIONS: &[&str] = &[
+    "readTextAsync",
+    "readBytesAsync",
+    "readJsonAsync",
+    "existsAsync",
+    "readDirAsync",
+];
+
+impl ModuleDef for FsModule {
+    fn declare(decl: &Declarations) -> rquickjs::Result<()> {
+        for name in SYNC_FUNCTIONS.iter().chain(ASYNC_FUNCTIONS) {
+            decl.declare(*name)?;
+        }
+
+        Ok(())
+    }
+
+    fn evaluate<'js>(ctx: &Ctx<'js>, exports: &Exports<'js>) -> rquickjs::Result<()> {
+        let vfs = Arc::new(ctx.userdata::<ScriptVfs>().unwrap().clone());
+
+        export_operation(ctx, exports, &vfs, "readText", read_text)?;
+        export_operation(ctx, exports, &vfs, "readBytes", read_bytes)?;
+        export_operation(ctx, exports, &vfs, "readJson", read_json)?;
+        export_operation(ctx, exports, &vfs, "exists", exists)?;
+        export_operation(ctx, exports, &vfs, "readDir", read_dir)?;
+
+        Ok(())
+    }
+}
+
+/// Exports both the blocking form of `operation` under `name` and a form running on a
+/// background thread under `{name}Async`.
+fn export_operation<'js, T>(
+    ctx: &Ctx<'js>,
+    exports: &Exports<'js>,
+    vfs: &Arc<ScriptVfs>,
+    name: &str,
+    operation: fn(&VirtualFs, &str) -> Result<T, vfs::Error>,
+) -> rquickjs::Result<()>
+where
+    T: for<'a> IntoJs<'a> + Send + 'static,
+{
+    let sync_vfs = Arc::clone(vfs);
+    let sync = Function::new(
+        ctx.clone(),
+        move |ctx: Ctx<'js>, path: String| -> rquickjs::Result<Value<'js>> {
+            sync_vfs
+                .check(&path)
+                .and_then(|()| operation(&sync_vfs.vfs, &path))
+                .map_err(|err| Exception::throw_message(&ctx, &err.to_string()))?
+                .into_js(&ctx)
+        },
+    )?;
+
+    let async_name = format!("{}Async", name);
+    let async_vfs = Arc::clone(vfs);
+    let asynchronous = Function::new(ctx.clone(), move |ctx: Ctx<'js>, path: String| {
+        let vfs = Arc::clone(&async_vfs);
+        task::spawn(&ctx, move || {
+            vfs.check(&path)
+                .and_then(|()| operation(&vfs.vfs, &path))
+                .map_err(|err| err.to_string())
+        })
+    })?;
+
+    export_function(ctx, exports, NativeModule::Fs, name, sync)?;
+    export_function(ctx, exports, NativeModule::Fs, &async_name, asynchronous)?;
+
+    Ok(())
+}
+
+struct Bytes(Vec<u8>);
+
+impl<'js> IntoJs<'js> for Bytes {
+    fn into_js(self, ctx: &Ctx<'js>) -> rquickjs::Result<Value<'js>> {
+        ArrayBuffer::new(ctx.clone(), self.0)?.into_js(ctx)
+    }
+}
+
+struct Json(String);
+
+impl<'js> IntoJs<'js> for Json {
+    fn into_js(self, ctx: &Ctx<'js>) -> rquickjs::Result<Value<'js>> {
+        ctx.json_parse(self.0)
+    }
+}
+
+impl<'js> IntoJs<'js> for DirEntry {
+    fn into_js(self, ctx: &Ctx<'js>) -> rquickjs::Result<Value<'js>> {
+        let object = Object::new(ctx.clone())?;
+        object.set("name", self.name)?;
+        object.set("isDirectory", self.is_dir)?;
+
+        Ok(object.into_value())
+    }
+}
+
+fn read_text(vfs: &VirtualFs, path: &str) -> Result<String, vfs::Error> {
+    let data = vfs.read(path)?;
+
+    String::from_utf8(data).map_err(|error| vfs::Error::Read {
+        path_spec: path.to_string(),
+        error: std::io::Error::new(std::io::ErrorKind::InvalidData, error),
+    })
+}
+
+fn read_bytes(vfs: &VirtualFs, path: &str) -> Result<Bytes, vfs::Error> {
+    vfs.read(path).map(Bytes)
+}
+
+fn read_json(vfs: &VirtualFs, path: &str) -> Result<Json, vfs::Error> {
+    read_text(vfs, path).map(Json)
+}
+
+fn exists(vfs: &VirtualFs, path: &str) -> Result<bool, vfs::Error> {
+    vfs.exists(path)
+}
+
+fn read_dir(vfs: &VirtualFs, path: &str) -> Result<Vec<DirEntry>, vfs::Error> {
+    vfs.read_dir(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::js::{Context, ContextOptions};
+
+    #[test]
+    fn scripts_only_read_their_roots() {
+        let root = std::env::temp_dir().join(format!("nechto-fs-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("mods/a")).unwrap();
+        std::fs::create_dir_all(root.join("mods/b")).unwrap();
+        std::fs::write(root.join("mods/a/own.txt"), "own").unwrap();
+        std::fs::write(root.join("mods/b/other.txt"), "other").unwrap();
+        std::fs::write(
+            root.join("mods/a/init.js"),
+            "import { readText } from 'nechto:fs';
+            const read = (path) => { try { return readText(path); } catch { return null; } };
+            globalThis.results = [
+                read('$script/mods/a/own.txt'),
+                read('$script/mods/b/other.txt'),
+                read('$script/mods/a/../b/other.txt'),
+                read('$script/mods/ab/other.txt'),
+            ];",
+        )
+        .unwrap();
+
+        let mut vfs = VirtualFs::new();
+        vfs.add_search_path("$script", root.clone());
+
+        let context = Context::new(
+            Arc::new(vfs),
+            ContextOptions {
+                root: "$script/mods/a".to_string(),
+                fs_roots: vec!["$script/mods/a".to_string()],
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        let results: Vec<Option<String>> = context
+            .ctx
+            .with(|ctx| ctx.globals().get("results"))
+            .unwrap();
+
+        assert_eq!(results, [Some("own".to_string()), None, None, None]);
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn scripts_read_files_blocking_and_in_the_background() {
+        let root = std::env::temp_dir().join(format!("nechto-fs-read-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("data/levels")).unwrap();
+        std::fs::write(root.join("data/config.json"), r#"{"volume": 0.5}"#).unwrap();
+        std::fs::write(root.join("data/blob"), [1, 2, 3]).unwrap();
+        std::fs::write(
+            root.join("init.js"),
+            "import * as fs from 'nechto:fs';
+            const entries = fs.readDir('$script/data').map((entry) => `${entry.name}:${entry.isDirectory}`);
+            globalThis.sync = [
+                String(fs.readJson('$script/data/config.json').volume),
+                String(new Uint8Array(fs.readBytes('$script/data/blob'))),
+                String(fs.exists('$script/data/missing')),
+                entries.join(','),
+            ];
+            globalThis.background = [];
+            fs.readTextAsync('$script/data/config.json')
+                .then((text) => background.push(text))
+                .then(() => fs.readJsonAsync('$script/data/missing.json'))
+                .catch((error) => background.push(String(String(error).includes('missing.json'))));",
+        )
+        .unwrap();
+
+        let mut vfs = VirtualFs::new();
+        vfs.add_search_path("$script", root.clone());
+
+        let mut context = Context::new(
+            Arc::new(vfs),
+            ContextOptions {
+                root: "$script".to_string(),
+                fs_roots: vec!["$script/data".to_string()],
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        let sync: Vec<String> = context.ctx.with(|ctx| ctx.globals().get("sync")).unwrap();
+        assert_eq!(
+            sync,
+            [
+                "0.5",
+                "1,2,3",
+                "false",
+                "blob:false,config.json:false,levels:true"
+            ]
+        );
+
+        let mut background: Vec<String> = Vec::new();
+
+        for _ in 0..200 {
+            context.update();
+            background = context
+                .ctx
+                .with(|ctx| ctx.globals().get("background"))
+                .unwrap();
+
+            if background.len() == 2 {
+                break;
+            }
+
+            std::thread::sleep(std::time::Duration::from_millis(5));
+        }
+
+        std::fs::remove_dir_all(root).unwrap();
+        assert_eq!(background, [r#"{"volume": 0.5}"#, "true"]);
+    }
+}
@@ -0,0 +1,690 @@
+use glam::EulerRot;
+use rquickjs::class::{JsClass, Trace, Tracer, Writable};
+use rquickjs::function::{Constructor, Opt, This};
+use rquickjs::module::{Declarations, Exports, ModuleDef};
+use rquickjs::object::Accessor;
+use rquickjs::{Class, Ctx, FromJs, IntoJs, JsLifetime, Object, Value};
+
+use crate::js::set_function;
+
+pub struct MathModule;
+
+impl ModuleDef for MathModule {
+    fn declare(decl: &Declarations) -> rquickjs::Result<()> {
+        decl.declare(Vec2::NAME)?;
+        decl.declare(Vec3::NAME)?;
+        decl.declare(Vec4::NAME)?;
+        decl.declare(Quat::NAME)?;
+        decl.declare(Mat4::NAME)?;
+
+        Ok(())
+    }
+
+    fn evaluate<'js>(ctx: &Ctx<'js>, exports: &Exports<'js>) -> rquickjs::Result<()> {
+        exports.export(Vec2::NAME, Class::<Vec2>::create_constructor(ctx)?)?;
+        exports.export(Vec3::NAME, Class::<Vec3>::create_constructor(ctx)?)?;
+        exports.export(Vec4::NAME, Class::<Vec4>::create_constructor(ctx)?)?;
+        exports.export(Quat::NAME, Class::<Quat>::create_constructor(ctx)?)?;
+        exports.export(Mat4::NAME, Class::<Mat4>::create_constructor(ctx)?)?;
+
+        Ok(())
+    }
+}
+
+/// Right-hand side of an arithmetic operation that accepts both scalars and values of the same
+/// type, e.g. `v.mul(2)` and `v.mul(other)`.
+enum Operand<T> {
+    Scalar(f32),
+    Value(T),
+}
+
+impl<'js, T: FromJs<'js>> FromJs<'js> for Operand<T> {
+    fn from_js(ctx: &Ctx<'js>, value: Value<'js>) -> rquickjs::Result<Self> {
+        match value.as_number() {
+            Some(scalar) => Ok(Operand::Scalar(scalar as f32)),
+            None => Ok(Operand::Value(T::from_js(ctx, value)?)),
+        }
+    }
+}
+
+/// Implements the plumbing shared by all math classes: the class is a `Copy` wrapper around a
+/// `glam` type that converts from either an instance of the class or a plain array of numbers,
+/// and into a new instance of the class.
+macro_rules! math_class {
+    ($class:ident, $inner:ty, $len:literal, $from_array:expr) => {
+        #[derive(Clone, Copy, Debug, PartialEq)]
+        pub struct $class(pub $inner);
+
+        impl<'js> Trace<'js> for $class {
+            fn trace<'a>(&self, _tracer: Tracer<'a, 'js>) {}
+        }
+
+        unsafe impl<'js> JsLifetime<'js> for $class {
+            type Changed<'to> = $class;
+        }
+
+        impl<'js> IntoJs<'js> for $class {
+            fn into_js(self, ctx: &Ctx<'js>) -> rquickjs::Result<Value<'js>> {
+                Class::instance(ctx.clone(), self)?.into_js(ctx)
+            }
+        }
+
+        impl<'js> FromJs<'js> for $class {
+            fn from_js(ctx: &Ctx<'js>, value: Value<'js>) -> rquickjs::Result<Self> {
+                if let Ok(class) = Class::<Self>::from_value(&value) {
+                    return Ok(*class.borrow());
+                }
+
+                let array = Vec::<f32>::from_js(ctx, value)?;
+                let array: [f32; $len] = array.try_into().map_err(|_| {
+                    rquickjs::Error::new_from_js_message(
+                        "array",
+                        stringify!($class),
+                        concat!("expected ", $len, " components"),
+                    )
+                })?;
+
+                Ok(Self($from_array(array)))
+            }
+        }
+
+        impl From<$inner> for $class {
+            fn from(value: $inner) -> Self {
+                Self(value)
+            }
+        }
+
+        impl From<$class> for $inner {
+            fn from(value: $class) -> Self {
+                value.0
+            }
+        }
+    };
+}
+
+macro_rules! define_components {
+    ($proto:expr, $class:ident, [$($component:ident),*]) => {
+        $(
+            $proto.prop(
+                stringify!($component),
+                Accessor::new(
+                    |this: This<$class>| this.0.0.$component,
+                    |this: This<Class<$class>>, value: f32| {
+                        this.0.borrow_mut().0.$component = value;
+                    },
+                )
+                .enumerable(),
+            )?;
+        )*
+    };
+}
+
+macro_rules! define_vector_methods {
+    ($ctx:expr, $proto:expr, $class:ident) => {{
+        let (ctx, proto): (&Ctx, &Object) = ($ctx, $proto);
+
+        set_function(
+            ctx.clone(),
+            proto,
+            "add",
+            |this: This<$class>, rhs: Operand<$class>| match rhs {
+                Operand::Scalar(rhs) => $class(this.0.0 + rhs),
+                Operand::Value(rhs) => $class(this.0.0 + rhs.0),
+            },
+        );
+        set_function(
+            ctx.clone(),
+            proto,
+            "sub",
+            |this: This<$class>, rhs: Operand<$class>| match rhs {
+                Operand::Scalar(rhs) => $class(this.0.0 - rhs),
+                Operand::Value(rhs) => $class(this.0.0 - rhs.0),
+            },
+        );
+        set_function(
+            ctx.clone(),
+            proto,
+            "mul",
+            |this: This<$class>, rhs: Operand<$class>| match rhs {
+                Operand::Scalar(rhs) => $class(this.0.0 * rhs),
+                Operand::Value(rhs) => $class(this.0.0 * rhs.0),
+            },
+        );
+        set_function(
+            ctx.clone(),
+            proto,
+            "div",
+            |this: This<$class>, rhs: Operand<$class>| match rhs {
+                Operand::Scalar(rhs) => $class(this.0.0 / rhs),
+                Operand::Value(rhs) => $class(this.0.0 / rhs.0),
+            },
+        );
+        set_function(ctx.clone(), proto, "neg", |this: This<$class>| {
+            $class(-this.0.0)
+        });
+        set_function(
+            ctx.clone(),
+            proto,
+            "dot",
+            |this: This<$class>, rhs: $class| this.0.0.dot(rhs.0),
+        );
+        set_function(ctx.clone(), proto, "length", |this: This<$class>| {
+            this.0.0.length()
+        });
+        set_function(ctx.clone(), proto, "lengthSquared", |this: This<$class>| {
+            this.0.0.length_squared()
+        });
+        set_function(
+            ctx.clone(),
+            proto,
+            "distance",
+            |this: This<$class>, rhs: $class| this.0.0.distance(rhs.0),
+        );
+        set_function(ctx.clone(), proto, "normalize", |this: This<$class>| {
+            $class(this.0.0.normalize_or_zero())
+        });
+        set_function(
+            ctx.clone(),
+            proto,
+            "lerp",
+            |this: This<$class>, rhs: $class, t: f32| $class(this.0.0.lerp(rhs.0, t)),
+        );
+        set_function(
+            ctx.clone(),
+            proto,
+            "min",
+            |this: This<$class>, rhs: $class| $class(this.0.0.min(rhs.0)),
+        );
+        set_function(
+            ctx.clone(),
+            proto,
+            "max",
+            |this: This<$class>, rhs: $class| $class(this.0.0.max(rhs.0)),
+        );
+        set_function(ctx.clone(), proto, "abs", |this: This<$class>| {
+            $class(this.0.0.abs())
+        });
+        set_function(
+            ctx.clone(),
+            proto,
+            "equals",
+            |this: This<$class>, rhs: $class| this.0 == rhs,
+        );
+        set_function(ctx.clone(), proto, "clone", |this: This<$class>| this.0);
+        set_function(ctx.clone(), proto, "toArray", |this: This<$class>| {
+            this.0.0.to_array().to_vec()
+        });
+        set_function(ctx.clone(), proto, "toString", |this: This<$class>| {
+            format!("{}{}", stringify!($class), this.0.0)
+        });
+    }};
+}
+
+macro_rules! define_from_array {
+    ($ctx:expr, $constructor:expr, $class:ident) => {
+        set_function($ctx, $constructor, "fromArray", |value: $class| value);
+    };
+}
+
+math_class!(Vec2, glam::Vec2, 2, glam::Vec2::from_array);
+math_class!(Vec3, glam::Vec3, 3, glam::Vec3::from_array);
+math_class!(Vec4, glam::Vec4, 4, glam::Vec4::from_array);
+math_class!(Quat, glam::Quat, 4, glam::Quat::from_array);
+math_class!(Mat4, glam::Mat4, 16, |array| glam::Mat4::from_cols_array(
+    &array
+));
+
+impl<'js> JsClass<'js> for Vec2 {
+    const NAME: &'static str = "Vec2";
+
+    type Mutable = Writable;
+
+    fn prototype(ctx: &Ctx<'js>) -> rquickjs::Result<Option<Object<'js>>> {
+        let proto = Object::new(ctx.clone())?;
+
+        define_components!(proto, Vec2, [x, y]);
+        define_vector_methods!(ctx, &proto, Vec2);
+
+        set_function(ctx.clone(), &proto, "perp", |this: This<Vec2>| {
+            Vec2(this.0.0.perp())
+        });
+        set_function(
+            ctx.clone(),
+            &proto,
+            "angleTo",
+            |this: This<Vec2>, rhs: Vec2| this.0.0.angle_to(rhs.0),
+        );
+
+        Ok(Some(proto))
+    }
+
+    fn constructor(ctx: &Ctx<'js>) -> rquickjs::Result<Option<Constructor<'js>>> {
+        let constructor =
+            Constructor::new_class::<Vec2, _, _>(ctx.clone(), |x: Opt<f32>, y: Opt<f32>| {
+                Vec2(glam::vec2(x.0.unwrap_or(0.0), y.0.unwrap_or(0.0)))
+            })?;
+
+        define_from_array!(ctx.clone(), &constructor, Vec2);
+
+        Ok(Some(constructor))
+    }
+}
+
+impl<'js> JsClass<'js> for Vec3 {
+    const NAME: &'static str = "Vec3";
+
+    type Mutable = Writable;
+
+    fn prototype(ctx: &Ctx<'js>) -> rquickjs::Result<Option<Object<'js>>> {
+        let proto = Object::new(ctx.clone())?;
+
+        define_components!(proto, Vec3, [x, y, z]);
+        define_vector_methods!(ctx, &proto, Vec3);
+
+        set_function(
+            ctx.clone(),
+            &proto,
+            "cross",
+            |this: This<Vec3>, rhs: Vec3| Vec3(this.0.0.cross(rhs.0)),
+        );
+        set_function(
+            ctx.clone(),
+            &proto,
+            "angleBetween",
+            |this: This<Vec3>, rhs: Vec3| this.0.0.angle_between(rhs.0),
+        );
+
+        Ok(Some(proto))
+    }
+
+    fn constructor(ctx: &Ctx<'js>) -> rquickjs::Result<Option<Constructor<'js>>> {
+        let constructor = Constructor::new_class::<Vec3, _, _>(
+            ctx.clone(),
+            |x: Opt<f32>, y: Opt<f32>, z: Opt<f32>| {
+                Vec3(glam::vec3(
+                    x.0.unwrap_or(0.0),
+                    y.0.unwrap_or(0.0),
+                    z.0.unwrap_or(0.0),
+                ))
+            },
+        )?;
+
+        define_from_array!(ctx.clone(), &constructor, Vec3);
+
+        Ok(Some(constructor))
+    }
+}
+
+impl<'js> JsClass<'js> for Vec4 {
+    const NAME: &'static str = "Vec4";
+
+    type Mutable = Writable;
+
+    fn prototype(ctx: &Ctx<'js>) -> rquickjs::Result<Option<Object<'js>>> {
+        let proto = Object::new(ctx.clone())?;
+
+        define_components!(proto, Vec4, [x, y, z, w]);
+        define_vector_methods!(ctx, &proto, Vec4);
+
+        set_function(ctx.clone(), &proto, "truncate", |this: This<Vec4>| {
+            Vec3(this.0.0.truncate())
+        });
+
+        Ok(Some(proto))
+    }
+
+    fn constructor(ctx: &Ctx<'js>) -> rquickjs::Result<Option<Constructor<'js>>> {
+        let constructor = Constructor::new_class::<Vec4, _, _>(
+            ctx.clone(),
+            |x: Opt<f32>, y: Opt<f32>, z: Opt<f32>, w: Opt<f32>| {
+                Vec4(glam::vec4(
+                    x.0.unwrap_or(0.0),
+                    y.0.unwrap_or(0.0),
+                    z.0.unwrap_or(0.0),
+                    w.0.unwrap_or(0.0),
+                ))
+            },
+        )?;
+
+        define_from_array!(ctx.clone(), &constructor, Vec4);
+
+        Ok(Some(constructor))
+    }
+}
+
+impl<'js> JsClass<'js> for Quat {
+    const NAME: &'static str = "Quat";
+
+    type Mutable = Writable;
+
+    fn prototype(ctx: &Ctx<'js>) -> rquickjs::Result<Option<Object<'js>>> {
+        let proto = Object::new(ctx.clone())?;
+
+        define_components!(proto, Quat, [x, y, z, w]);
+
+        // Multiplying by a quaternion composes rotations, multiplying by a vector rotates it.
+        set_function(
+            ctx.clone(),
+            &proto,
+            "mul",
+            |ctx: Ctx<'js>, this: This<Quat>, rhs: Value<'js>| {
+                if let Ok(rhs) = Class::<Quat>::from_value(&rhs) {
+                    return Quat(this.0.0 * rhs.borrow().0).into_js(&ctx);
+                }
+
+                Vec3(this.0.0 * Vec3::from_js(&ctx, rhs)?.0).into_js(&ctx)
+            },
+        );
+        set_function(ctx.clone(), &proto, "inverse", |this: This<Quat>| {
+            Quat(this.0.0.inverse())
+        });
+        set_function(ctx.clone(), &proto, "normalize", |this: This<Quat>| {
+            Quat(this.0.0.normalize())
+        });
+        set_function(ctx.clone(), &proto, "length", |this: This<Quat>| {
+            this.0.0.length()
+        });
+        set_function(ctx.clone(), &proto, "dot", |this: This<Quat>, rhs: Quat| {
+            this.0.0.dot(rhs.0)
+        });
+        set_function(
+            ctx.clone(),
+            &proto,
+            "slerp",
+            |this: This<Quat>, rhs: Quat, t: f32| Quat(this.0.0.slerp(rhs.0, t)),
+        );
+        set_function(
+            ctx.clone(),
+            &proto,
+            "angleBetween",
+            |this: This<Quat>, rhs: Quat| this.0.0.angle_between(rhs.0),
+        );
+        set_function(ctx.clone(), &proto, "toEuler", |this: This<Quat>| {
+            let (x, y, z) = this.0.0.to_euler(EulerRot::XYZ);
+            Vec3(glam::vec3(x, y, z))
+        });
+        set_function(
+            ctx.clone(),
+            &proto,
+            "equals",
+            |this: This<Quat>, rhs: Quat| this.0 == rhs,
+        );
+        set_function(ctx.clone(), &proto, "clone", |this: This<Quat>| this.0);
+        set_function(ctx.clone(), &proto, "toArray", |this: This<Quat>| {
+            this.0.0.to_array().to_vec()
+        });
+        set_function(ctx.clone(), &proto, "toString", |this: This<Quat>| {
+            format!("Quat{}", this.0.0)
+        });
+
+        Ok(Some(proto))
+    }
+
+    fn constructor(ctx: &Ctx<'js>) -> rquickjs::Result<Option<Constructor<'js>>> {
+        let constructor = Constructor::new_class::<Quat, _, _>(
+            ctx.clone(),
+            |x: Opt<f32>, y: Opt<f32>, z: Opt<f32>, w: Opt<f32>| {
+                Quat(glam::Quat::from_xyzw(
+                    x.0.unwrap_or(0.0),
+                    y.0.unwrap_or(0.0),
+                    z.0.unwrap_or(0.0),
+                    w.0.unwrap_or(1.0),
+                ))
+            },
+        )?;
+
+        define_from_array!(ctx.clone(), &constructor, Quat);
+
+        set_function(ctx.clone(), &constructor, "identity", || {
+            Quat(glam::Quat::IDENTITY)
+        });
+        set_function(
+            ctx.clone(),
+            &constructor,
+            "fromAxisAngle",
+            |axis: Vec3, angle: f32| {
+                Quat(glam::Quat::from_axis_angle(
+                    axis.0.normalize_or_zero(),
+                    angle,
+                ))
+            },
+        );
+        set_function(
+            ctx.clone(),
+            &constructor,
+            "fromEuler",
+            |x: f32, y: f32, z: f32| Quat(glam::Quat::from_euler(EulerRot::XYZ, x, y, z)),
+        );
+        set_function(
+            ctx.clone(),
+            &constructor,
+            "fromRotationArc",
+            |from: Vec3, to: Vec3| {
+                Quat(glam::Quat::from_rotation_arc(
+                    from.0.normalize_or_zero(),
+                    to.0.normalize_or_zero(),
+                ))
+            },
+        );
+
+        Ok(Some(constructor))
+    }
+}
+
+impl<'js> JsClass<'js> for Mat4 {
+    const NAME: &'static str = "Mat4";
+
+    type Mutable = Writable;
+
+    fn prototype(ctx: &Ctx<'js>) -> rquickjs::Result<Option<Object<'js>>> {
+        let proto = Object::new(ctx.clone())?;
+
+        // Multiplying by a matrix composes transforms, multiplying by a vector transforms it.
+        set_function(
+            ctx.clone(),
+            &proto,
+            "mul",
+            |ctx: Ctx<'js>, this: This<Mat4>, rhs: Value<'js>| {
+                if let Ok(rhs) = Class::<Mat4>::from_value(&rhs) {
+                    return Mat4(this.0.0 * rhs.borrow().0).into_js(&ctx);
+                }
+
+                Vec4(this.0.0 * Vec4::from_js(&ctx, rhs)?.0).into_js(&ctx)
+            },
+        );
+        set_function(
+            ctx.clone(),
+            &proto,
+            "transformPoint",
+            |this: This<Mat4>, point: Vec3| Vec3(this.0.0.transform_point3(point.0)),
+        );
+        set_function(
+            ctx.clone(),
+            &proto,
+            "transformVector",
+            |this: This<Mat4>, vector: Vec3| Vec3(this.0.0.transform_vector3(vector.0)),
+        );
+        set_function(ctx.clone(), &proto, "inverse", |this: This<Mat4>| {
+            Mat4(this.0.0.inverse())
+        });
+        set_function(ctx.clone(), &proto, "transpose", |this: This<Mat4>| {
+            Mat4(this.0.0.transpose())
+        });
+        set_function(ctx.clone(), &proto, "determinant", |this: This<Mat4>| {
+            this.0.0.determinant()
+        });
+        set_function(
+            ctx.clone(),
+            &proto,
+            "column",
+            |this: This<Mat4>, index: usize| (index < 4).then(|| Vec4(this.0.0.col(index))),
+        );
+        set_function(
+            ctx.clone(),
+            &proto,
+            "row",
+            |this: This<Mat4>, index: usize| (index < 4).then(|| Vec4(this.0.0.row(index))),
+        );
+        set_function(
+            ctx.clone(),
+            &proto,
+            "decompose",
+            |ctx: Ctx<'js>, this: This<Mat4>| {
+                let (scale, rotation, translation) = this.0.0.to_scale_rotation_translation();
+
+                let object = Object::new(ctx)?;
+                object.set("scale", Vec3(scale))?;
+                object.set("rotation", Quat(rotation))?;
+                object.set("translation", Vec3(translation))?;
+
+                rquickjs::Result::Ok(object)
+            },
+        );
+        set_function(
+            ctx.clone(),
+            &proto,
+            "equals",
+            |this: This<Mat4>, rhs: Mat4| this.0 == rhs,
+        );
+        set_function(ctx.clone(), &proto, "clone", |this: This<Mat4>| this.0);
+        set_function(ctx.clone(), &proto, "toArray", |this: This<Mat4>| {
+            this.0.0.to_cols_array().to_vec()
+        });
+        set_function(ctx.clone(), &proto, "toString", |this: This<Mat4>| {
+            format!("Mat4{}", this.0.0)
+        });
+
+        Ok(Some(proto))
+    }
+
+    fn constructor(ctx: &Ctx<'js>) -> rquickjs::Result<Option<Constructor<'js>>> {
+        let constructor =
+            Constructor::new_class::<Mat4, _, _>(ctx.clone(), || Mat4(glam::Mat4::IDENTITY))?;
+
+        define_from_array!(ctx.clone(), &constructor, Mat4);
+
+        set_function(ctx.clone(), &constructor, "identity", || {
+            Mat4(glam::Mat4::IDENTITY)
+        });
+        set_function(
+            ctx.clone(),
+            &constructor,
+            "fromTranslation",
+            |translation: Vec3| Mat4(glam::Mat4::from_translation(translation.0)),
+        );
+        set_function(ctx.clone(), &constructor, "fromQuat", |rotation: Quat| {
+            Mat4(glam::Mat4::from_quat(rotation.0))
+        });
+        set_function(ctx.clone(), &constructor, "fromScale", |scale: Vec3| {
+            Mat4(glam::Mat4::from_scale(scale.0))
+        });
+        set_function(
+            ctx.clone(),
+            &constructor,
+            "fromScaleRotationTranslation",
+            |scale: Vec3, rotation: Quat, translation: Vec3| {
+                Mat4(glam::Mat4::from_scale_rotation_translation(
+                    scale.0,
+                    rotation.0,
+                    translation.0,
+                ))
+            },
+        );
+        set_function(
+            ctx.clone(),
+            &constructor,
+            "perspective",
+            |fov_y: f32, aspect_ratio: f32, z_near: f32, z_far: f32| {
+                Mat4(glam::Mat4::perspective_rh(
+                    fov_y,
+                    aspect_ratio,
+                    z_near,
+                    z_far,
+                ))
+            },
+        );
+        set_function(
+            ctx.clone(),
+            &constructor,
+            "orthographic",
+            |left: f32, right: f32, bottom: f32, top: f32, near: f32, far: f32| {
+                Mat4(glam::Mat4::orthographic_rh(
+                    left, right, bottom, top, near, far,
+                ))
+            },
+        );
+        set_function(
+            ctx.clone(),
+            &constructor,
+            "lookAt",
+            |eye: Vec3, center: Vec3, up: Vec3| Mat4(glam::Mat4::look_at_rh(eye.0, center.0, up.0)),
+        );
+
+        Ok(Some(constructor))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::js::{Context, ContextOptions};
+    use crate::vfs::VirtualFs;
+
+    #[test]
+    fn scripts_compute_with_math_classes() {
+        let root = std::env::temp_dir().join(format!("nechto-math-{}", std::process::id()));
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(
+            root.join("init.js"),
+            "import { Vec2, Vec3, Quat, Mat4 } from 'nechto:math';
+            const position = new Vec2();
+            position.x = 3;
+            position.y = 4;
+            globalThis.sum = new Vec3(1, 2, 3).add(1).mul([2, 1, 0.5]);
+            globalThis.rotated = Quat.fromAxisAngle([0, 0, 1], Math.PI / 2).mul([1, 0, 0]);
+            globalThis.moved = Mat4.fromTranslation([1, 2, 3]).mul(new Mat4()).transformPoint(new Vec3());
+            globalThis.length = position.length();
+            globalThis.identity = new Quat().equals(Quat.identity());
+            globalThis.text = Vec2.fromArray([1, 2]).toString();
+            globalThis.invalid = (() => { try { new Vec3().add([1, 2]); } catch { return true; } })();",
+        )
+        .unwrap();
+
+        let mut vfs = VirtualFs::new();
+        vfs.add_search_path("$script", root.clone());
+
+        let context = Context::new(
+            Arc::new(vfs),
+            ContextOptions {
+                root: "$script".to_string(),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        std::fs::remove_dir_all(root).unwrap();
+
+        context.ctx.with(|ctx| {
+            let globals = ctx.globals();
+
+            let sum: Vec3 = globals.get("sum").unwrap();
+            assert_eq!(sum.0, glam::vec3(4.0, 3.0, 2.0));
+
+            let rotated: Vec3 = globals.get("rotated").unwrap();
+            assert!(rotated.0.abs_diff_eq(glam::Vec3::Y, 1e-6));
+
+            let moved: Vec3 = globals.get("moved").unwrap();
+            assert_eq!(moved.0, glam::vec3(1.0, 2.0, 3.0));
+
+            assert_eq!(globals.get::<_, f32>("length").unwrap(), 5.0);
+            assert!(globals.get::<_, bool>("identity").unwrap());
+            assert_eq!(
+                globals.get::<_, String>("text").unwrap(),
+                format!("Vec2{}", glam::vec2(1.0, 2.0))
+            );
+            assert!(globals.get::<_, bool>("invalid").unwrap());
+        });
+    }
+}
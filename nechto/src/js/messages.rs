@@ -0,0 +1,137 @@
+use std::cell::RefCell;
+
+use rquickjs::function::Opt;
+use rquickjs::module::{Declarations, Exports, ModuleDef};
+use rquickjs::{Ctx, Function, IntoJs, JsLifetime, Object, Value};
+use tracing::error;
+
+use crate::event::Payload;
+use crate::js::{NativeModule, export_function, profiler};
+
+/// A message sent from one script context to another.
+#[derive(Debug, Clone)]
+pub struct Message {
+    pub from: String,
+    pub to: String,
+    pub payload: Payload,
+}
+
+/// Message handlers and messages posted by scripts, stored as context userdata.
+pub(crate) struct ScriptMessages<'js> {
+    name: String,
+    handlers: RefCell<Vec<Function<'js>>>,
+    outbox: RefCell<Vec<Message>>,
+}
+
+unsafe impl<'js> JsLifetime<'js> for ScriptMessages<'js> {
+    type Changed<'to> = ScriptMessages<'to>;
+}
+
+impl<'js> ScriptMessages<'js> {
+    pub fn new(name: String) -> Self {
+        Self {
+            name,
+            handlers: RefCell::new(Vec::new()),
+            outbox: RefCell::new(Vec::new()),
+        }
+    }
+}
+
+pub struct MessagesModule;
+
+impl ModuleDef for MessagesModule {
+    fn declare(decl: &Declarations) -> rquickjs::Result<()> {
+        decl.declare("contextName")?;
+        decl.declare("post")?;
+        decl.declare("onMessage")?;
+        decl.declare("offMessage")?;
+
+        Ok(())
+    }
+
+    fn evaluate<'js>(ctx: &Ctx<'js>, exports: &Exports<'js>) -> rquickjs::Result<()> {
+        let name = ctx.userdata::<ScriptMessages>().unwrap().name.clone();
+
+        exports.export("contextName", name)?;
+        export_function(
+            ctx,
+            exports,
+            NativeModule::Messages,
+            "post",
+            Function::new(ctx.clone(), post)?,
+        )?;
+        export_function(
+            ctx,
+            exports,
+            NativeModule::Messages,
+            "onMessage",
+            Function::new(ctx.clone(), on_message)?,
+        )?;
+        export_function(
+            ctx,
+            exports,
+            NativeModule::Messages,
+            "offMessage",
+            Function::new(ctx.clone(), off_message)?,
+        )?;
+
+        Ok(())
+    }
+}
+
+fn post<'js>(ctx: Ctx<'js>, to: String, payload: Opt<Payload>) {
+    let messages = ctx.userdata::<ScriptMessages>().unwrap();
+    messages.outbox.borrow_mut().push(Message {
+        from: messages.name.clone(),
+        to,
+        payload: payload.0.unwrap_or_default(),
+    });
+}
+
+fn on_message<'js>(ctx: Ctx<'js>, handler: Function<'js>) {
+    let messages = ctx.userdata::<ScriptMessages>().unwrap();
+    messages.handlers.borrow_mut().push(handler);
+}
+
+fn off_message<'js>(ctx: Ctx<'js>, handler: Function<'js>) {
+    let messages = ctx.userdata::<ScriptMessages>().unwrap();
+    messages
+        .handlers
+        .borrow_mut()
+        .retain(|existing| *existing != handler);
+}
+
+pub(crate) fn take_posted(ctx: &Ctx) -> Vec<Message> {
+    let messages = ctx.userdata::<ScriptMessages>().unwrap();
+    messages.outbox.take()
+}
+
+pub(crate) fn deliver(ctx: &Ctx, message: &Message) {
+    let handlers = {
+        let messages = ctx.userdata::<ScriptMessages>().unwrap();
+        messages.handlers.borrow().clone()
+    };
+
+    for handler in handlers {
+        let _scope = profiler::scope(ctx, || format!("message from {}", message.from));
+
+        let result =
+            message_object(ctx, message).and_then(|message| handler.call::<_, ()>((message,)));
+
+        if let Err(err) = result {
+            let exception = ctx.catch();
+            error!(
+                "script message handler for message from {} failed: {} {:?}",
+                message.from, err, exception
+            );
+        }
+    }
+}
+
+fn message_object<'js>(ctx: &Ctx<'js>, message: &Message) -> rquickjs::Result<Value<'js>> {
+    let object = Object::new(ctx.clone())?;
+    object.set("from", message.from.as_str())?;
+    object.set("payload", message.payload.clone().into_js(ctx)?)?;
+
+    Ok(object.into_value())
+}
@@ -1,33 +1,174 @@
 mod console;
+mod contexts;
+mod declarations;
+mod events;
+mod fs;
+mod math;
+mod messages;
+mod profiler;
+mod task;
 
-use std::path::Path;
+pub use self::contexts::Contexts;
+pub use self::declarations::TYPE_DECLARATIONS;
+pub use self::math::{Mat4, Quat, Vec2, Vec3, Vec4};
+pub use self::messages::Message;
+pub use self::profiler::{CallbackTiming, ProfileReport, ProfilerOptions};
+
+use std::path::{Component, Path, PathBuf};
 use std::sync::Arc;
 
-use rquickjs::module::Declared;
-use rquickjs::{CatchResultExt, Function, Module, Value};
+use rquickjs::function::IntoJsFunc;
+use rquickjs::loader::{BuiltinResolver, ModuleLoader};
+use rquickjs::module::{Declared, Exports};
+use rquickjs::{CatchResultExt, Ctx, Function, Module, Object, Value};
+use tracing::error;
 
+use crate::event::RawEvent;
 use crate::js::console::console_object;
+use crate::js::events::{EventsModule, ScriptEvents};
+use crate::js::fs::{FsModule, ScriptVfs};
+use crate::js::math::MathModule;
+use crate::js::messages::{MessagesModule, ScriptMessages};
+use crate::js::profiler::Profiler;
+use crate::js::task::Tasks;
 use crate::vfs::VirtualFs;
 
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("script context {0} already exists")]
+    DuplicateContext(String),
+
+    #[error("script context {context}: {message}")]
+    Exception { context: String, message: String },
+}
+
+/// Modules implemented natively that a context may be allowed to import.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NativeModule {
+    Events,
+    Fs,
+    Math,
+    Messages,
+}
+
+impl NativeModule {
+    pub const ALL: &[NativeModule] = &[
+        NativeModule::Events,
+        NativeModule::Fs,
+        NativeModule::Math,
+        NativeModule::Messages,
+    ];
+
+    pub fn specifier(&self) -> &'static str {
+        match self {
+            NativeModule::Events => "nechto:events",
+            NativeModule::Fs => "nechto:fs",
+            NativeModule::Math => "nechto:math",
+            NativeModule::Messages => "nechto:messages",
+        }
+    }
+
+    fn add_to_loader(&self, loader: &mut ModuleLoader) {
+        let specifier = self.specifier();
+
+        match self {
+            NativeModule::Events => loader.add_module(specifier, EventsModule),
+            NativeModule::Fs => loader.add_module(specifier, FsModule),
+            NativeModule::Math => loader.add_module(specifier, MathModule),
+            NativeModule::Messages => loader.add_module(specifier, MessagesModule),
+        };
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ContextOptions {
+    /// Unique name of the context, used as the address for messages.
+    pub name: String,
+    /// Path spec of the directory scripts are imported from.
+    pub root: String,
+    /// Module imported when the context is created, relative to `root`.
+    pub entry: String,
+    /// Directories scripts may read through `nechto:fs`, as path specs like `$data` or
+    /// `$data/mods/foo`.
+    pub fs_roots: Vec<String>,
+    pub native_modules: Vec<NativeModule>,
+    /// Records time spent in callbacks from the engine and in native module functions if set,
+    /// and samples script call stacks. See `Context::take_profile_report`.
+    pub profiler: Option<ProfilerOptions>,
+}
+
+impl Default for ContextOptions {
+    fn default() -> Self {
+        Self {
+            name: "main".to_string(),
+            root: "$build/script".to_string(),
+            entry: "init".to_string(),
+            fs_roots: vec!["$data".to_string(), "$user".to_string()],
+            native_modules: NativeModule::ALL.to_vec(),
+            profiler: None,
+        }
+    }
+}
+
 pub struct Context {
+    name: String,
     runtime: rquickjs::Runtime,
     ctx: rquickjs::Context,
+    profiler: Option<Profiler>,
 }
 
 impl Context {
-    pub fn new(vfs: Arc<VirtualFs>) -> Self {
+    /// Creates a context and evaluates its entry module, returning the exception if the module
+    /// fails to load or throws.
+    pub fn new(vfs: Arc<VirtualFs>, options: ContextOptions) -> Result<Self, Error> {
         let runtime = rquickjs::Runtime::new().unwrap();
         let ctx = rquickjs::Context::full(&runtime).unwrap();
 
+        let profiler = options.profiler.map(Profiler::new);
+
+        if let Some(profiler) = &profiler {
+            runtime.set_interrupt_handler(profiler.interrupt_handler(ctx.as_raw()));
+        }
+
+        let mut builtin_resolver = BuiltinResolver::default();
+        let mut module_loader = ModuleLoader::default();
+
+        for module in &options.native_modules {
+            builtin_resolver.add_module(module.specifier());
+            module.add_to_loader(&mut module_loader);
+        }
+
         runtime.set_loader(
-            VfsResolver {
-                root: "$build/script".to_string(),
-                vfs: Arc::clone(&vfs),
-            },
-            VfsLoader { vfs },
+            (
+                builtin_resolver,
+                VfsResolver {
+                    root: options.root.clone(),
+                    vfs: Arc::clone(&vfs),
+                },
+            ),
+            (
+                module_loader,
+                VfsLoader {
+                    vfs: Arc::clone(&vfs),
+                },
+            ),
         );
 
-        ctx.with(|ctx| {
+        ctx.with(|ctx| -> Result<(), Error> {
+            ctx.store_userdata(ScriptVfs {
+                vfs,
+                roots: options.fs_roots.clone(),
+            })
+            .unwrap();
+            ctx.store_userdata(Tasks::new()).unwrap();
+            ctx.store_userdata(ScriptEvents::new()).unwrap();
+            ctx.store_userdata(ScriptMessages::new(options.name.clone()))
+                .unwrap();
+
+            if let Some(profiler) = &profiler {
+                ctx.store_userdata(profiler.clone()).unwrap();
+            }
+
             let globals = ctx.globals();
 
             globals.set("console", console_object(ctx.clone())).unwrap();
@@ -44,29 +185,146 @@ impl Context {
                 )
                 .unwrap();
 
-            Module::evaluate(
-                ctx.clone(),
-                "main",
-                r#"
-                    import 'init';
-                "#,
-            )
-            .catch(&ctx)
-            .unwrap()
-            .finish::<()>()
-            .catch(&ctx)
-            .unwrap();
+            let _scope = profiler::scope(&ctx, || format!("module {}", options.entry));
+
+            let exception = |err: rquickjs::CaughtError| Error::Exception {
+                context: options.name.clone(),
+                message: err.to_string(),
+            };
+
+            Module::evaluate(ctx.clone(), "main", format!("import '{}';", options.entry))
+                .catch(&ctx)
+                .map_err(exception)?
+                .finish::<()>()
+                .catch(&ctx)
+                .map_err(exception)
+        })?;
+
+        Ok(Self {
+            name: options.name,
+            runtime,
+            ctx,
+            profiler,
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Calls message handlers registered by scripts of this context.
+    pub fn deliver_message(&mut self, message: &Message) {
+        self.ctx.with(|ctx| messages::deliver(&ctx, message));
+    }
+
+    /// Returns messages posted by scripts since the last call.
+    pub fn take_posted_messages(&mut self) -> Vec<Message> {
+        self.ctx.with(|ctx| messages::take_posted(&ctx))
+    }
+
+    /// Calls script listeners subscribed to the given events.
+    pub fn dispatch_events<'a>(&mut self, events: impl IntoIterator<Item = &'a RawEvent>) {
+        self.ctx.with(|ctx| {
+            for event in events {
+                events::dispatch(&ctx, event);
+            }
         });
+    }
+
+    /// Returns events emitted by scripts since the last call.
+    pub fn take_emitted_events(&mut self) -> Vec<RawEvent> {
+        self.ctx.with(|ctx| events::take_emitted(&ctx))
+    }
 
-        Self { runtime, ctx }
+    /// Settles promises of finished background tasks and runs pending script jobs.
+    pub fn update(&mut self) {
+        self.ctx.with(|ctx| task::poll(&ctx));
+
+        let _scope = self
+            .profiler
+            .as_ref()
+            .map(|profiler| profiler.enter("jobs".to_string()));
+
+        loop {
+            match self.runtime.execute_pending_job() {
+                Ok(true) => {}
+                Ok(false) => break,
+                Err(err) => err.0.with(|ctx| {
+                    error!("uncaught exception in script job: {:?}", ctx.catch());
+                }),
+            }
+        }
+    }
+
+    /// Returns timings recorded since the previous call, or `None` if the context isn't profiled.
+    pub fn take_profile_report(&mut self) -> Option<ProfileReport> {
+        self.profiler.as_ref().map(Profiler::take_report)
     }
 }
 
+/// Exports a function of a native module, instrumented for the profiler.
+pub(crate) fn export_function<'js>(
+    ctx: &Ctx<'js>,
+    exports: &Exports<'js>,
+    module: NativeModule,
+    name: &str,
+    function: Function<'js>,
+) -> rquickjs::Result<()> {
+    let function = function.with_name(name)?;
+    let label = format!("{}/{}", module.specifier(), name);
+
+    exports.export(name, profiler::instrument(ctx, &label, function)?)?;
+
+    Ok(())
+}
+
+pub(crate) fn set_function<'a, P, F>(ctx: Ctx<'a>, object: &Object<'a>, name: &str, func: F)
+where
+    F: IntoJsFunc<'a, P> + 'a,
+{
+    object
+        .set(
+            name,
+            Function::new(ctx, func).unwrap().with_name(name).unwrap(),
+        )
+        .unwrap();
+}
+
 pub struct VfsResolver {
     root: String,
     vfs: Arc<VirtualFs>,
 }
 
+impl VfsResolver {
+    /// The VFS path of a module, or `None` if the specifier leads outside the root, which would
+    /// let a context import the modules of another one.
+    fn module_path(&self, name: &str) -> Option<String> {
+        let mut path = PathBuf::from(&self.root);
+        let mut depth = 0;
+
+        for component in Path::new(name).components() {
+            match component {
+                Component::Normal(part) => {
+                    path.push(part);
+                    depth += 1;
+                }
+                Component::CurDir => {}
+                Component::ParentDir if depth > 0 => {
+                    path.pop();
+                    depth -= 1;
+                }
+                _ => return None,
+            }
+        }
+
+        if depth == 0 {
+            return None;
+        }
+
+        Some(path.with_extension("js").to_str()?.to_owned())
+    }
+}
+
 impl rquickjs::loader::Resolver for VfsResolver {
     fn resolve(
         &mut self,
@@ -74,16 +332,17 @@ impl rquickjs::loader::Resolver for VfsResolver {
         base: &str,
         name: &str,
     ) -> rquickjs::Result<String> {
-        let path = Path::new(&self.root).join(name).with_extension("js");
-        let path = path.to_str().unwrap();
+        let path = self
+            .module_path(name)
+            .ok_or_else(|| rquickjs::Error::new_resolving(base, name))?;
 
         let exists = self
             .vfs
-            .exists(path)
+            .exists(path.as_str())
             .map_err(|_| rquickjs::Error::new_resolving(base, name))?;
 
         if exists {
-            Ok(path.to_string())
+            Ok(path)
         } else {
             Err(rquickjs::Error::new_resolving(base, name))
         }
@@ -108,3 +367,28 @@ impl rquickjs::loader::Loader for VfsLoader {
         Module::declare(ctx.clone(), name, source)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn module_paths_stay_under_the_root() {
+        let resolver = VfsResolver {
+            root: "$build/script/mod".to_string(),
+            vfs: Arc::new(VirtualFs::new()),
+        };
+
+        let path = |name| resolver.module_path(name);
+
+        assert_eq!(path("init").as_deref(), Some("$build/script/mod/init.js"));
+        assert_eq!(
+            path("./lib/../util").as_deref(),
+            Some("$build/script/mod/util.js")
+        );
+        assert_eq!(path("../other/secret"), None);
+        assert_eq!(path("lib/../../other/secret"), None);
+        assert_eq!(path("/etc/passwd"), None);
+        assert_eq!(path("."), None);
+    }
+}
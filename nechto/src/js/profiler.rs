@@ -0,0 +1,337 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::ffi::CStr;
+use std::fmt;
+use std::ptr::NonNull;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use rquickjs::{Ctx, Function, JsLifetime, qjs};
+use tracing::span::EnteredSpan;
+use tracing::trace_span;
+
+#[derive(Debug, Clone, Default)]
+pub struct ProfilerOptions {
+    /// How often the script call stack is sampled, sampling is disabled if `None`.
+    pub sample_interval: Option<Duration>,
+}
+
+/// Time spent in one kind of callback: module evaluation, event and message listeners, settled
+/// tasks, pending jobs, or a function of a native module. Functions defined by scripts are not
+/// timed, only sampled. Nested callbacks are included in the time of their caller.
+#[derive(Debug, Clone)]
+pub struct CallbackTiming {
+    pub label: String,
+    pub calls: u64,
+    pub total: Duration,
+    pub max: Duration,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ProfileReport {
+    /// Sorted by total time, slowest first.
+    pub timings: Vec<CallbackTiming>,
+    /// Sampled script call stacks in folded format (`outer;inner`, each frame as
+    /// `function (file)`) with sample counts, most frequent first.
+    pub samples: Vec<(String, u64)>,
+}
+
+impl fmt::Display for ProfileReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{:>8} {:>12} {:>12} {:>12}  callback",
+            "calls", "total", "mean", "max"
+        )?;
+
+        for timing in &self.timings {
+            writeln!(
+                f,
+                "{:>8} {:>12.3?} {:>12.3?} {:>12.3?}  {}",
+                timing.calls,
+                timing.total,
+                timing.total / timing.calls.max(1) as u32,
+                timing.max,
+                timing.label
+            )?;
+        }
+
+        if !self.samples.is_empty() {
+            writeln!(f, "{:>8}  sampled stack", "samples")?;
+
+            for (stack, count) in &self.samples {
+                writeln!(f, "{:>8}  {}", count, stack)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+struct ProfilerState {
+    stack: Vec<(String, Instant)>,
+    timings: HashMap<String, CallbackTiming>,
+    samples: HashMap<String, u64>,
+    sample_interval: Option<Duration>,
+    last_sample: Instant,
+}
+
+/// Records time spent in script callbacks of one context, stored as context userdata.
+#[derive(Clone)]
+pub(crate) struct Profiler(Arc<Mutex<ProfilerState>>);
+
+unsafe impl<'js> JsLifetime<'js> for Profiler {
+    type Changed<'to> = Profiler;
+}
+
+impl Profiler {
+    pub fn new(options: ProfilerOptions) -> Self {
+        Self(Arc::new(Mutex::new(ProfilerState {
+            stack: Vec::new(),
+            timings: HashMap::new(),
+            samples: HashMap::new(),
+            sample_interval: options.sample_interval,
+            last_sample: Instant::now(),
+        })))
+    }
+
+    /// Returns a handler for `rquickjs::Runtime::set_interrupt_handler` that samples the script
+    /// call stack of `ctx`, or `None` if sampling is disabled. The runtime must only run scripts
+    /// of `ctx`.
+    pub fn interrupt_handler(
+        &self,
+        ctx: NonNull<qjs::JSContext>,
+    ) -> Option<rquickjs::runtime::InterruptHandler> {
+        let interval = self.0.lock().unwrap().sample_interval?;
+        let state = Arc::clone(&self.0);
+
+        Some(Box::new(move || {
+            let due = {
+                let mut state = state.lock().unwrap();
+                let now = Instant::now();
+                let due = now.duration_since(state.last_sample) >= interval;
+
+                if due {
+                    state.last_sample = now;
+                }
+
+                due
+            };
+
+            if !due {
+                return false;
+            }
+
+            // Not locked while capturing: allocating the stack can run finalizers that drop
+            // scopes of this profiler. The handler is only called while the runtime executes a
+            // script of `ctx`.
+            let frames = unsafe { script_stack(ctx.as_ptr()) };
+
+            let stack = match frames.is_empty() {
+                true => "(script)".to_string(),
+                false => frames.join(";"),
+            };
+
+            *state.lock().unwrap().samples.entry(stack).or_default() += 1;
+
+            false
+        }))
+    }
+
+    /// Starts timing a callback, the time is recorded when the returned scope is dropped.
+    pub fn enter(&self, label: String) -> Scope {
+        let span = trace_span!("script", callback = label.as_str()).entered();
+
+        self.0.lock().unwrap().stack.push((label, Instant::now()));
+
+        Scope {
+            profiler: self.clone(),
+            _span: span,
+        }
+    }
+
+    /// Returns what was recorded since the previous call.
+    pub fn take_report(&self) -> ProfileReport {
+        let mut state = self.0.lock().unwrap();
+
+        let mut timings: Vec<_> = state.timings.drain().map(|(_, timing)| timing).collect();
+        timings.sort_by_key(|timing| std::cmp::Reverse(timing.total));
+
+        let mut samples: Vec<_> = state.samples.drain().collect();
+        samples.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+
+        ProfileReport { timings, samples }
+    }
+}
+
+/// The call stack of the script running in `ctx`, outermost frame first.
+///
+/// # Safety
+///
+/// `ctx` must be a valid context whose runtime is executing a script on this thread.
+unsafe fn script_stack(ctx: *mut qjs::JSContext) -> Vec<String> {
+    unsafe {
+        // New errors capture the backtrace of the current stack frames as their `stack`.
+        let error = qjs::JS_NewError(ctx);
+
+        if qjs::JS_IsException(error) {
+            qjs::JS_FreeValue(ctx, qjs::JS_GetException(ctx));
+            return Vec::new();
+        }
+
+        let stack = qjs::JS_GetPropertyStr(ctx, error, c"stack".as_ptr());
+        qjs::JS_FreeValue(ctx, error);
+
+        if qjs::JS_IsException(stack) {
+            qjs::JS_FreeValue(ctx, qjs::JS_GetException(ctx));
+            return Vec::new();
+        }
+
+        let text = qjs::JS_ToCString(ctx, stack);
+        qjs::JS_FreeValue(ctx, stack);
+
+        if text.is_null() {
+            qjs::JS_FreeValue(ctx, qjs::JS_GetException(ctx));
+            return Vec::new();
+        }
+
+        let frames = folded_frames(&CStr::from_ptr(text).to_string_lossy());
+        qjs::JS_FreeCString(ctx, text);
+
+        frames
+    }
+}
+
+/// Converts a backtrace of `at function (file:line:column)` lines, innermost first, to frames
+/// of `function (file)`, outermost first.
+fn folded_frames(backtrace: &str) -> Vec<String> {
+    let mut frames: Vec<String> = backtrace
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix("at "))
+        .map(|frame| match frame.split_once(" (") {
+            Some((function, location)) => {
+                let location = location.trim_end_matches(')');
+                let file = location.split(':').next().unwrap_or(location);
+                format!("{} ({})", function, file)
+            }
+            None => frame.to_string(),
+        })
+        .collect();
+
+    frames.reverse();
+    frames
+}
+
+pub(crate) struct Scope {
+    profiler: Profiler,
+    _span: EnteredSpan,
+}
+
+impl Drop for Scope {
+    fn drop(&mut self) {
+        let mut state = self.profiler.0.lock().unwrap();
+        let Some((label, start)) = state.stack.pop() else {
+            return;
+        };
+
+        let elapsed = start.elapsed();
+
+        let timing = state
+            .timings
+            .entry(label)
+            .or_insert_with_key(|label| CallbackTiming {
+                label: label.clone(),
+                calls: 0,
+                total: Duration::ZERO,
+                max: Duration::ZERO,
+            });
+
+        timing.calls += 1;
+        timing.total += elapsed;
+        timing.max = timing.max.max(elapsed);
+    }
+}
+
+/// Starts timing a callback if the context is profiled. `label` is only called in that case.
+pub(crate) fn scope(ctx: &Ctx, label: impl FnOnce() -> String) -> Option<Scope> {
+    let profiler = ctx.userdata::<Profiler>()?.clone();
+
+    Some(profiler.enter(label()))
+}
+
+/// Wraps a native function so that calls from scripts are timed under `label` if the context
+/// is profiled, otherwise returns it unchanged.
+///
+/// The wrapper is a script function: a Rust closure holding `function` would keep the context
+/// alive through a reference the garbage collector can't see.
+pub(crate) fn instrument<'js>(
+    ctx: &Ctx<'js>,
+    label: &str,
+    function: Function<'js>,
+) -> rquickjs::Result<Function<'js>> {
+    let Some(profiler) = ctx.userdata::<Profiler>().map(|profiler| profiler.clone()) else {
+        return Ok(function);
+    };
+
+    let name: String = function.get("name")?;
+    let label = label.to_string();
+
+    let scopes = Rc::new(RefCell::new(Vec::new()));
+    let enter_scopes = Rc::clone(&scopes);
+
+    let enter = Function::new(ctx.clone(), move || {
+        enter_scopes
+            .borrow_mut()
+            .push(profiler.enter(label.clone()));
+    })?;
+    let leave = Function::new(ctx.clone(), move || {
+        scopes.borrow_mut().pop();
+    })?;
+
+    let wrap: Function = ctx.eval(
+        "(inner, enter, leave) => function (...args) { \
+            enter(); \
+            try { return inner.apply(this, args); } finally { leave(); } \
+        }",
+    )?;
+
+    wrap.call::<_, Function>((function, enter, leave))?
+        .with_name(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn samples_script_stacks() {
+        let runtime = rquickjs::Runtime::new().unwrap();
+        let ctx = rquickjs::Context::full(&runtime).unwrap();
+
+        let profiler = Profiler::new(ProfilerOptions {
+            sample_interval: Some(Duration::ZERO),
+        });
+        runtime.set_interrupt_handler(profiler.interrupt_handler(ctx.as_raw()));
+
+        ctx.with(|ctx| {
+            ctx.eval::<(), _>(
+                "function hot() { let x = 0; for (let i = 0; i < 1e6; i++) x += i; return x; } \
+                 function main() { return hot(); } \
+                 main();",
+            )
+            .unwrap();
+        });
+
+        let report = profiler.take_report();
+
+        assert!(
+            report
+                .samples
+                .iter()
+                .any(|(stack, _)| stack.ends_with("main (eval_script);hot (eval_script)")),
+            "{:?}",
+            report.samples
+        );
+    }
+}
@@ -0,0 +1,108 @@
+use std::cell::RefCell;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+
+use rquickjs::{Ctx, Exception, Function, IntoJs, JsLifetime, Promise, Value};
+use tracing::error;
+
+use crate::js::profiler;
+
+type Completion = Box<dyn for<'js> FnOnce(&Ctx<'js>) -> rquickjs::Result<Value<'js>> + Send>;
+
+struct PendingTask<'js> {
+    receiver: Receiver<Result<Completion, String>>,
+    resolve: Function<'js>,
+    reject: Function<'js>,
+}
+
+/// Work running on background threads whose results settle promises on the script thread.
+///
+/// Stored as context userdata and polled by `js::Context::update`.
+pub(crate) struct Tasks<'js> {
+    pending: RefCell<Vec<PendingTask<'js>>>,
+}
+
+unsafe impl<'js> JsLifetime<'js> for Tasks<'js> {
+    type Changed<'to> = Tasks<'to>;
+}
+
+impl<'js> Tasks<'js> {
+    pub fn new() -> Self {
+        Self {
+            pending: RefCell::new(Vec::new()),
+        }
+    }
+}
+
+/// Runs `task` on a background thread and returns a promise that is settled with its result
+/// during the next `js::Context::update` after the task finishes.
+pub(crate) fn spawn<'js, F, T>(ctx: &Ctx<'js>, task: F) -> rquickjs::Result<Promise<'js>>
+where
+    F: FnOnce() -> Result<T, String> + Send + 'static,
+    T: for<'a> IntoJs<'a> + Send + 'static,
+{
+    let (promise, resolve, reject) = ctx.promise()?;
+    let (sender, receiver) = mpsc::channel();
+
+    std::thread::spawn(move || {
+        let result = task().map(|value| -> Completion { Box::new(move |ctx| value.into_js(ctx)) });
+
+        // The receiver is gone only if the context was dropped, nobody is waiting then.
+        let _ = sender.send(result);
+    });
+
+    let tasks = ctx.userdata::<Tasks>().unwrap();
+    tasks.pending.borrow_mut().push(PendingTask {
+        receiver,
+        resolve,
+        reject,
+    });
+
+    Ok(promise)
+}
+
+pub(crate) fn poll(ctx: &Ctx) {
+    let tasks = ctx.userdata::<Tasks>().unwrap();
+
+    let mut finished = Vec::new();
+
+    tasks
+        .pending
+        .borrow_mut()
+        .retain_mut(|task| match task.receiver.try_recv() {
+            Ok(result) => {
+                finished.push((result, task.resolve.clone(), task.reject.clone()));
+                false
+            }
+            Err(TryRecvError::Empty) => true,
+            Err(TryRecvError::Disconnected) => {
+                finished.push((
+                    Err("task panicked".to_string()),
+                    task.resolve.clone(),
+                    task.reject.clone(),
+                ));
+                false
+            }
+        });
+
+    // Settle after releasing the borrow: resolving may run code that spawns new tasks.
+    drop(tasks);
+
+    for (result, resolve, reject) in finished {
+        let _scope = profiler::scope(ctx, || "task".to_string());
+
+        let value = match result {
+            Ok(completion) => completion(ctx),
+            Err(message) => Err(Exception::throw_message(ctx, &message)),
+        };
+
+        let settled = match value {
+            Ok(value) => resolve.call::<_, ()>((value,)),
+            Err(rquickjs::Error::Exception) => reject.call::<_, ()>((ctx.catch(),)),
+            Err(err) => reject.call::<_, ()>((err.to_string(),)),
+        };
+
+        if let Err(err) = settled {
+            error!("unable to settle script promise: {}", err);
+        }
+    }
+}
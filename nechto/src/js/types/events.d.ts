@@ -0,0 +1,13 @@
+declare module "nechto:events" {
+    export type Payload =
+        | null
+        | boolean
+        | number
+        | string
+        | Payload[]
+        | { [key: string]: Payload };
+
+    export function on(name: string, callback: (payload: any) => void): void;
+    export function off(name: string, callback: (payload: any) => void): void;
+    export function emit(name: string, payload?: Payload): void;
+}
@@ -0,0 +1,18 @@
+declare module "nechto:fs" {
+    export interface DirEntry {
+        name: string;
+        isDirectory: boolean;
+    }
+
+    export function readText(path: string): string;
+    export function readBytes(path: string): ArrayBuffer;
+    export function readJson(path: string): any;
+    export function exists(path: string): boolean;
+    export function readDir(path: string): DirEntry[];
+
+    export function readTextAsync(path: string): Promise<string>;
+    export function readBytesAsync(path: string): Promise<ArrayBuffer>;
+    export function readJsonAsync(path: string): Promise<any>;
+    export function existsAsync(path: string): Promise<boolean>;
+    export function readDirAsync(path: string): Promise<DirEntry[]>;
+}
@@ -0,0 +1,5 @@
+declare const console: {
+    log(...values: unknown[]): void;
+};
+
+declare function print(value: unknown): void;
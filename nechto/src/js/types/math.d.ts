@@ -0,0 +1,152 @@
+declare module "nechto:math" {
+    export type Vec2Like = Vec2 | [number, number];
+    export type Vec3Like = Vec3 | [number, number, number];
+    export type Vec4Like = Vec4 | [number, number, number, number];
+    export type QuatLike = Quat | [number, number, number, number];
+    export type Mat4Like = Mat4 | number[];
+
+    export class Vec2 {
+        constructor(x?: number, y?: number);
+        static fromArray(value: Vec2Like): Vec2;
+
+        x: number;
+        y: number;
+
+        add(rhs: number | Vec2Like): Vec2;
+        sub(rhs: number | Vec2Like): Vec2;
+        mul(rhs: number | Vec2Like): Vec2;
+        div(rhs: number | Vec2Like): Vec2;
+        neg(): Vec2;
+        dot(rhs: Vec2Like): number;
+        length(): number;
+        lengthSquared(): number;
+        distance(rhs: Vec2Like): number;
+        normalize(): Vec2;
+        lerp(rhs: Vec2Like, t: number): Vec2;
+        min(rhs: Vec2Like): Vec2;
+        max(rhs: Vec2Like): Vec2;
+        abs(): Vec2;
+        perp(): Vec2;
+        angleTo(rhs: Vec2Like): number;
+        equals(rhs: Vec2Like): boolean;
+        clone(): Vec2;
+        toArray(): [number, number];
+        toString(): string;
+    }
+
+    export class Vec3 {
+        constructor(x?: number, y?: number, z?: number);
+        static fromArray(value: Vec3Like): Vec3;
+
+        x: number;
+        y: number;
+        z: number;
+
+        add(rhs: number | Vec3Like): Vec3;
+        sub(rhs: number | Vec3Like): Vec3;
+        mul(rhs: number | Vec3Like): Vec3;
+        div(rhs: number | Vec3Like): Vec3;
+        neg(): Vec3;
+        dot(rhs: Vec3Like): number;
+        cross(rhs: Vec3Like): Vec3;
+        length(): number;
+        lengthSquared(): number;
+        distance(rhs: Vec3Like): number;
+        normalize(): Vec3;
+        lerp(rhs: Vec3Like, t: number): Vec3;
+        min(rhs: Vec3Like): Vec3;
+        max(rhs: Vec3Like): Vec3;
+        abs(): Vec3;
+        angleBetween(rhs: Vec3Like): number;
+        equals(rhs: Vec3Like): boolean;
+        clone(): Vec3;
+        toArray(): [number, number, number];
+        toString(): string;
+    }
+
+    export class Vec4 {
+        constructor(x?: number, y?: number, z?: number, w?: number);
+        static fromArray(value: Vec4Like): Vec4;
+
+        x: number;
+        y: number;
+        z: number;
+        w: number;
+
+        add(rhs: number | Vec4Like): Vec4;
+        sub(rhs: number | Vec4Like): Vec4;
+        mul(rhs: number | Vec4Like): Vec4;
+        div(rhs: number | Vec4Like): Vec4;
+        neg(): Vec4;
+        dot(rhs: Vec4Like): number;
+        length(): number;
+        lengthSquared(): number;
+        distance(rhs: Vec4Like): number;
+        normalize(): Vec4;
+        lerp(rhs: Vec4Like, t: number): Vec4;
+        min(rhs: Vec4Like): Vec4;
+        max(rhs: Vec4Like): Vec4;
+        abs(): Vec4;
+        truncate(): Vec3;
+        equals(rhs: Vec4Like): boolean;
+        clone(): Vec4;
+        toArray(): [number, number, number, number];
+        toString(): string;
+    }
+
+    export class Quat {
+        constructor(x?: number, y?: number, z?: number, w?: number);
+        static fromArray(value: QuatLike): Quat;
+        static identity(): Quat;
+        static fromAxisAngle(axis: Vec3Like, angle: number): Quat;
+        static fromEuler(x: number, y: number, z: number): Quat;
+        static fromRotationArc(from: Vec3Like, to: Vec3Like): Quat;
+
+        x: number;
+        y: number;
+        z: number;
+        w: number;
+
+        mul(rhs: Quat): Quat;
+        mul(rhs: Vec3Like): Vec3;
+        inverse(): Quat;
+        normalize(): Quat;
+        length(): number;
+        dot(rhs: QuatLike): number;
+        slerp(rhs: QuatLike, t: number): Quat;
+        angleBetween(rhs: QuatLike): number;
+        toEuler(): Vec3;
+        equals(rhs: QuatLike): boolean;
+        clone(): Quat;
+        toArray(): [number, number, number, number];
+        toString(): string;
+    }
+
+    export class Mat4 {
+        constructor();
+        static fromArray(value: Mat4Like): Mat4;
+        static identity(): Mat4;
+        static fromTranslation(translation: Vec3Like): Mat4;
+        static fromQuat(rotation: QuatLike): Mat4;
+        static fromScale(scale: Vec3Like): Mat4;
+        static fromScaleRotationTranslation(scale: Vec3Like, rotation: QuatLike, translation: Vec3Like): Mat4;
+        static perspective(fovY: number, aspectRatio: number, zNear: number, zFar: number): Mat4;
+        static orthographic(left: number, right: number, bottom: number, top: number, near: number, far: number): Mat4;
+        static lookAt(eye: Vec3Like, center: Vec3Like, up: Vec3Like): Mat4;
+
+        mul(rhs: Mat4): Mat4;
+        mul(rhs: Vec4Like): Vec4;
+        transformPoint(point: Vec3Like): Vec3;
+        transformVector(vector: Vec3Like): Vec3;
+        inverse(): Mat4;
+        transpose(): Mat4;
+        determinant(): number;
+        column(index: number): Vec4 | undefined;
+        row(index: number): Vec4 | undefined;
+        decompose(): { scale: Vec3; rotation: Quat; translation: Vec3 };
+        equals(rhs: Mat4Like): boolean;
+        clone(): Mat4;
+        toArray(): number[];
+        toString(): string;
+    }
+}
@@ -0,0 +1,12 @@
+declare module "nechto:messages" {
+    export interface Message {
+        from: string;
+        payload: any;
+    }
+
+    export const contextName: string;
+
+    export function post(to: string, payload?: import("nechto:events").Payload): void;
+    export function onMessage(handler: (message: Message) => void): void;
+    export function offMessage(handler: (message: Message) => void): void;
+}
@@ -4,6 +4,7 @@
 pub mod asset;
 pub mod collections;
 pub mod config;
+pub mod event;
 pub mod gpu;
 pub mod handle;
 pub mod input;
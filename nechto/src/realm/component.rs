@@ -0,0 +1,290 @@
+use std::any::{Any, TypeId, type_name};
+use std::cell::UnsafeCell;
+use std::collections::HashMap;
+
+use crate::handle::Handle;
+use crate::realm::Object;
+
+/// Data attached to an object, at most one of each type per object.
+pub trait Component: Any {}
+
+/// A component of any type, boxed until it is stored in the column of its type.
+pub(super) trait AnyComponent: Any {
+    fn insert_into(self: Box<Self>, components: &mut Components, handle: Handle<Object>);
+}
+
+impl<T: Component> AnyComponent for T {
+    fn insert_into(self: Box<Self>, components: &mut Components, handle: Handle<Object>) {
+        components.insert(handle, *self);
+    }
+}
+
+/// Components of one type, packed in one array.
+pub struct Column<T> {
+    /// Index of the component of each object handle index in `values`.
+    value_indexes: Vec<Option<u32>>,
+    /// Mutable through a shared column only while the realm is borrowed mutably by a query.
+    values: Vec<UnsafeCell<T>>,
+    /// Object of each value.
+    owners: Vec<Handle<Object>>,
+}
+
+impl<T> Column<T> {
+    fn new() -> Self {
+        Self {
+            value_indexes: Vec::new(),
+            values: Vec::new(),
+            owners: Vec::new(),
+        }
+    }
+
+    fn value_index(&self, handle: Handle<Object>) -> Option<usize> {
+        let index = (*self.value_indexes.get(handle.to_usize())?)? as usize;
+        (self.owners[index] == handle).then_some(index)
+    }
+
+    fn get(&self, handle: Handle<Object>) -> Option<&T> {
+        let index = self.value_index(handle)?;
+        Some(unsafe { &*self.values[index].get() })
+    }
+
+    fn get_mut(&mut self, handle: Handle<Object>) -> Option<&mut T> {
+        let index = self.value_index(handle)?;
+        Some(self.values[index].get_mut())
+    }
+
+    /// # Safety
+    ///
+    /// No other reference to the component may exist while the returned one is used.
+    #[allow(clippy::mut_from_ref)]
+    unsafe fn get_unchecked_mut(&self, handle: Handle<Object>) -> Option<&mut T> {
+        let index = self.value_index(handle)?;
+        Some(unsafe { &mut *self.values[index].get() })
+    }
+
+    /// Inserts or replaces the component of an object, returning the replaced one.
+    fn insert(&mut self, handle: Handle<Object>, value: T) -> Option<T> {
+        if let Some(index) = self.value_index(handle) {
+            return Some(std::mem::replace(self.values[index].get_mut(), value));
+        }
+
+        let value_index = u32::try_from(self.values.len()).expect("column is full");
+
+        if self.value_indexes.len() <= handle.to_usize() {
+            self.value_indexes.resize(handle.to_usize() + 1, None);
+        }
+
+        self.values.push(UnsafeCell::new(value));
+        self.owners.push(handle);
+        self.value_indexes[handle.to_usize()] = Some(value_index);
+
+        None
+    }
+
+    fn remove(&mut self, handle: Handle<Object>) -> Option<T> {
+        let value_index = self.value_index(handle)?;
+        self.value_indexes[handle.to_usize()] = None;
+
+        let value = self.values.swap_remove(value_index).into_inner();
+        self.owners.swap_remove(value_index);
+
+        if let Some(moved) = self.owners.get(value_index) {
+            self.value_indexes[moved.to_usize()] = Some(value_index as u32);
+        }
+
+        Some(value)
+    }
+
+    /// Objects with a component in this column, in storage order.
+    pub fn owners(&self) -> &[Handle<Object>] {
+        &self.owners
+    }
+}
+
+/// A column of any component type.
+trait AnyColumn: Any {
+    fn remove_boxed(&mut self, handle: Handle<Object>) -> Option<Box<dyn AnyComponent>>;
+}
+
+impl<T: Component> AnyColumn for Column<T> {
+    fn remove_boxed(&mut self, handle: Handle<Object>) -> Option<Box<dyn AnyComponent>> {
+        Some(Box::new(self.remove(handle)?))
+    }
+}
+
+/// Components of all objects of a realm, stored in one column per type.
+pub struct Components {
+    columns: HashMap<TypeId, Box<dyn AnyColumn>>,
+}
+
+impl Components {
+    pub(super) fn new() -> Self {
+        Self {
+            columns: HashMap::new(),
+        }
+    }
+
+    pub fn column<T: Component>(&self) -> Option<&Column<T>> {
+        let column = self.columns.get(&TypeId::of::<T>())?.as_ref() as &dyn Any;
+        column.downcast_ref()
+    }
+
+    fn column_mut<T: Component>(&mut self) -> Option<&mut Column<T>> {
+        let column = self.columns.get_mut(&TypeId::of::<T>())?.as_mut() as &mut dyn Any;
+        column.downcast_mut()
+    }
+
+    pub(super) fn get<T: Component>(&self, handle: Handle<Object>) -> Option<&T> {
+        self.column::<T>()?.get(handle)
+    }
+
+    pub(super) fn get_mut<T: Component>(&mut self, handle: Handle<Object>) -> Option<&mut T> {
+        self.column_mut::<T>()?.get_mut(handle)
+    }
+
+    pub(super) fn insert<T: Component>(&mut self, handle: Handle<Object>, value: T) -> Option<T> {
+        let column = self
+            .columns
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| Box::new(Column::<T>::new()))
+            .as_mut() as &mut dyn Any;
+
+        column
+            .downcast_mut::<Column<T>>()
+            .unwrap()
+            .insert(handle, value)
+    }
+
+    pub(super) fn remove<T: Component>(&mut self, handle: Handle<Object>) -> Option<T> {
+        self.column_mut::<T>()?.remove(handle)
+    }
+
+    /// Removes all components of an object.
+    pub(super) fn remove_all(&mut self, handle: Handle<Object>) -> Vec<Box<dyn AnyComponent>> {
+        self.columns
+            .values_mut()
+            .filter_map(|column| column.remove_boxed(handle))
+            .collect()
+    }
+}
+
+/// Components fetched together by `Realm::query`: a component reference like `&Camera` or
+/// `&mut Camera`, or a tuple of them like `(&Transform, &mut Light)`.
+///
+/// # Safety
+///
+/// `access` must list every component type `fetch` returns a reference to, mutable ones with
+/// `true`.
+pub unsafe trait Query {
+    type Item<'a>;
+    type Columns<'a>: Copy;
+
+    fn access(access: &mut Vec<(TypeId, &'static str, bool)>);
+
+    /// The columns of the query, or `None` if no object has one of the components.
+    fn columns(components: &Components) -> Option<Self::Columns<'_>>;
+
+    /// Objects to fetch, those of the smallest column.
+    fn owners<'a>(columns: Self::Columns<'a>) -> &'a [Handle<Object>];
+
+    /// # Safety
+    ///
+    /// Components fetched mutably must not be referenced elsewhere while the item is used.
+    unsafe fn fetch<'a>(
+        columns: Self::Columns<'a>,
+        handle: Handle<Object>,
+    ) -> Option<Self::Item<'a>>;
+}
+
+/// A query that only fetches shared references, allowed on a shared realm.
+pub trait ReadOnlyQuery: Query {}
+
+unsafe impl<T: Component> Query for &T {
+    type Item<'a> = &'a T;
+    type Columns<'a> = &'a Column<T>;
+
+    fn access(access: &mut Vec<(TypeId, &'static str, bool)>) {
+        access.push((TypeId::of::<T>(), type_name::<T>(), false));
+    }
+
+    fn columns(components: &Components) -> Option<Self::Columns<'_>> {
+        components.column::<T>()
+    }
+
+    fn owners<'a>(column: Self::Columns<'a>) -> &'a [Handle<Object>] {
+        column.owners()
+    }
+
+    unsafe fn fetch<'a>(column: Self::Columns<'a>, handle: Handle<Object>) -> Option<&'a T> {
+        column.get(handle)
+    }
+}
+
+impl<T: Component> ReadOnlyQuery for &T {}
+
+unsafe impl<T: Component> Query for &mut T {
+    type Item<'a> = &'a mut T;
+    type Columns<'a> = &'a Column<T>;
+
+    fn access(access: &mut Vec<(TypeId, &'static str, bool)>) {
+        access.push((TypeId::of::<T>(), type_name::<T>(), true));
+    }
+
+    fn columns(components: &Components) -> Option<Self::Columns<'_>> {
+        components.column::<T>()
+    }
+
+    fn owners<'a>(column: Self::Columns<'a>) -> &'a [Handle<Object>] {
+        column.owners()
+    }
+
+    unsafe fn fetch<'a>(column: Self::Columns<'a>, handle: Handle<Object>) -> Option<&'a mut T> {
+        unsafe { column.get_unchecked_mut(handle) }
+    }
+}
+
+macro_rules! tuple_query {
+    ($($query:ident),+) => {
+        unsafe impl<$($query: Query),+> Query for ($($query,)+) {
+            type Item<'a> = ($($query::Item<'a>,)+);
+            type Columns<'a> = ($($query::Columns<'a>,)+);
+
+            fn access(access: &mut Vec<(TypeId, &'static str, bool)>) {
+                $($query::access(access);)+
+            }
+
+            fn columns(components: &Components) -> Option<Self::Columns<'_>> {
+                Some(($($query::columns(components)?,)+))
+            }
+
+            #[allow(non_snake_case)]
+            fn owners<'a>(columns: Self::Columns<'a>) -> &'a [Handle<Object>] {
+                let ($($query,)+) = columns;
+
+                [$($query::owners($query)),+]
+                    .into_iter()
+                    .min_by_key(|owners| owners.len())
+                    .unwrap()
+            }
+
+            #[allow(non_snake_case)]
+            unsafe fn fetch<'a>(
+                columns: Self::Columns<'a>,
+                handle: Handle<Object>,
+            ) -> Option<Self::Item<'a>> {
+                let ($($query,)+) = columns;
+
+                Some(($(unsafe { $query::fetch($query, handle)? },)+))
+            }
+        }
+
+        impl<$($query: ReadOnlyQuery),+> ReadOnlyQuery for ($($query,)+) {}
+    };
+}
+
+tuple_query!(A);
+tuple_query!(A, B);
+tuple_query!(A, B, C);
+tuple_query!(A, B, C, D);
+tuple_query!(A, B, C, D, E);
+tuple_query!(A, B, C, D, E, F);
@@ -1,23 +1,806 @@
+mod component;
 mod object;
+mod scene;
+mod transform;
 
-pub use self::object::{IntoObject, Object, ObjectData};
+pub use self::component::{Column, Component, Components, Query, ReadOnlyQuery};
+pub use self::object::{IntoObject, Object};
+pub use self::scene::{
+    Error as SceneError, FieldError, Fields, SCENE_VERSION, Scene, SceneComponent, SceneComponents,
+    SceneObject,
+};
+pub use self::transform::Transform;
+
+use std::any::TypeId;
+use std::collections::VecDeque;
+
+use glam::{Mat4, Vec3};
+use tracing::warn;
 
 use crate::collections::Pool;
 use crate::handle::Handle;
 
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("object {0:?} does not exist")]
+    InvalidObject(Handle<Object>),
+
+    #[error("object {parent:?} is a descendant of {child:?} and can't become its parent")]
+    Cycle {
+        child: Handle<Object>,
+        parent: Handle<Object>,
+    },
+}
+
+/// Objects linked into a hierarchy of parents and children. Objects without a parent are roots.
+///
+/// Components of the objects are stored in one column per type, and fetched in combination with
+/// queries like `realm.query::<(&Transform, &Camera)>()`.
 pub struct Realm {
     objects: Pool<Object>,
+    components: Components,
+    /// Objects whose transform or parent changed since the last `update_transforms`.
+    dirty_transforms: Vec<Handle<Object>>,
 }
 
 impl Realm {
     pub fn new() -> Self {
         Self {
             objects: Pool::new(),
+            components: Components::new(),
+            dirty_transforms: Vec::new(),
+        }
+    }
+
+    /// Adds an object and links it with the parent and children it was built with. Links to
+    /// objects that don't exist or would form a cycle are dropped.
+    pub fn add_object(&mut self, mut object: Object) -> Handle<Object> {
+        let parent = std::mem::replace(&mut object.parent, Handle::NIL);
+        let children = std::mem::take(&mut object.children);
+        let components = std::mem::take(&mut object.components);
+
+        // An object removed from a realm keeps the state of its old transform.
+        object.world_matrix = Mat4::IDENTITY;
+        object.transform_dirty = false;
+
+        let handle = self.objects.insert(object);
+
+        for component in components {
+            component.insert_into(&mut self.components, handle);
+        }
+
+        self.mark_transform_dirty(handle);
+
+        for child in children {
+            if let Err(err) = self.set_parent(child, handle) {
+                warn!("dropped child of added object: {}", err);
+            }
+        }
+
+        if !parent.is_nil()
+            && let Err(err) = self.set_parent(handle, parent)
+        {
+            warn!("dropped parent of added object: {}", err);
+        }
+
+        handle
+    }
+
+    pub fn get(&self, handle: Handle<Object>) -> Option<&Object> {
+        self.objects.get(handle)
+    }
+
+    pub fn get_mut(&mut self, handle: Handle<Object>) -> Option<&mut Object> {
+        self.objects.get_mut(handle)
+    }
+
+    pub fn objects(&self) -> impl Iterator<Item = (Handle<Object>, &Object)> {
+        self.objects.iter()
+    }
+
+    pub fn component<T: Component>(&self, handle: Handle<Object>) -> Option<&T> {
+        self.components.get(handle)
+    }
+
+    /// A mutable component, borrowing a `Transform` this way marks it dirty.
+    pub fn component_mut<T: Component>(&mut self, handle: Handle<Object>) -> Option<&mut T> {
+        if self.has_component::<T>(handle) {
+            self.mark_component_changed::<T>(handle);
+        }
+
+        self.components.get_mut(handle)
+    }
+
+    pub fn has_component<T: Component>(&self, handle: Handle<Object>) -> bool {
+        self.component::<T>(handle).is_some()
+    }
+
+    /// Inserts or replaces a component of an object, returning the replaced one. Returns the
+    /// component back if the object does not exist.
+    pub fn insert_component<T: Component>(
+        &mut self,
+        handle: Handle<Object>,
+        component: T,
+    ) -> Result<Option<T>, T> {
+        if !self.objects.contains(handle) {
+            return Err(component);
+        }
+
+        let replaced = self.components.insert(handle, component);
+        self.mark_component_changed::<T>(handle);
+
+        Ok(replaced)
+    }
+
+    pub fn remove_component<T: Component>(&mut self, handle: Handle<Object>) -> Option<T> {
+        let component = self.components.remove(handle)?;
+        self.mark_component_changed::<T>(handle);
+
+        Some(component)
+    }
+
+    fn mark_component_changed<T: Component>(&mut self, handle: Handle<Object>) {
+        if TypeId::of::<T>() == TypeId::of::<Transform>() {
+            self.mark_transform_dirty(handle);
+        }
+    }
+
+    /// All components of the storage column of `T`, for iterating without other components.
+    pub fn column<T: Component>(&self) -> Option<&Column<T>> {
+        self.components.column()
+    }
+
+    /// Objects that have all components of the query, with the components.
+    pub fn query<Q: ReadOnlyQuery>(&self) -> impl Iterator<Item = (Handle<Object>, Q::Item<'_>)> {
+        self.query_where::<Q>(|_| true)
+    }
+
+    /// Like `query`, but can fetch mutable components such as `(&Transform, &mut Camera)`.
+    /// Fetching `&mut Transform` marks the transforms of the fetched objects dirty.
+    ///
+    /// Panics if a component type is fetched mutably more than once.
+    pub fn query_mut<Q: Query>(&mut self) -> impl Iterator<Item = (Handle<Object>, Q::Item<'_>)> {
+        self.query_where_mut::<Q>(|_| true)
+    }
+
+    /// Objects that have all components of the query and for which `filter` returns true, for
+    /// example `|object| object.has_tag("enemy")`.
+    pub fn query_where<Q: ReadOnlyQuery>(
+        &self,
+        filter: impl Fn(&Object) -> bool,
+    ) -> impl Iterator<Item = (Handle<Object>, Q::Item<'_>)> {
+        // Shared fetches never alias mutably.
+        unsafe { self.fetch::<Q>(filter) }
+    }
+
+    pub fn query_where_mut<Q: Query>(
+        &mut self,
+        filter: impl Fn(&Object) -> bool,
+    ) -> impl Iterator<Item = (Handle<Object>, Q::Item<'_>)> {
+        let mut access = Vec::new();
+        Q::access(&mut access);
+
+        for (index, (type_id, name, mutable)) in access.iter().enumerate() {
+            let aliased = access[index + 1..]
+                .iter()
+                .any(|(other, _, other_mutable)| other == type_id && (*mutable || *other_mutable));
+
+            assert!(!aliased, "query fetches {} mutably more than once", name);
+        }
+
+        let writes_transform = access
+            .iter()
+            .any(|(type_id, _, mutable)| *mutable && *type_id == TypeId::of::<Transform>());
+
+        if writes_transform {
+            let fetched: Vec<Handle<Object>> = Q::columns(&self.components)
+                .map_or(&[][..], |columns| Q::owners(columns))
+                .iter()
+                .copied()
+                .filter(|handle| self.objects.get(*handle).is_some_and(&filter))
+                .collect();
+
+            for handle in fetched {
+                self.mark_transform_dirty(handle);
+            }
+        }
+
+        // The realm is borrowed mutably and each object is fetched once, so the only references
+        // to mutable components are those of the current item.
+        unsafe { self.fetch::<Q>(filter) }
+    }
+
+    /// # Safety
+    ///
+    /// Mutable components of the query must not be aliased.
+    unsafe fn fetch<Q: Query>(
+        &self,
+        filter: impl Fn(&Object) -> bool,
+    ) -> impl Iterator<Item = (Handle<Object>, Q::Item<'_>)> {
+        let columns = Q::columns(&self.components);
+        let owners = columns.map_or(&[][..], |columns| Q::owners(columns));
+
+        owners.iter().filter_map(move |&handle| {
+            if !filter(self.objects.get(handle)?) {
+                return None;
+            }
+
+            Some((handle, unsafe { Q::fetch(columns?, handle)? }))
+        })
+    }
+
+    /// The first object with the name.
+    pub fn find(&self, name: &str) -> Option<Handle<Object>> {
+        self.objects
+            .iter()
+            .find(|(_, object)| object.name() == name)
+            .map(|(handle, _)| handle)
+    }
+
+    pub fn tagged<'a>(&'a self, tag: &'a str) -> impl Iterator<Item = Handle<Object>> + 'a {
+        self.objects
+            .iter()
+            .filter(move |(_, object)| object.has_tag(tag))
+            .map(|(handle, _)| handle)
+    }
+
+    pub fn roots(&self) -> impl Iterator<Item = Handle<Object>> {
+        self.objects
+            .iter()
+            .filter(|(_, object)| object.parent.is_nil())
+            .map(|(handle, _)| handle)
+    }
+
+    /// Moves an object under a new parent, or makes it a root if the parent is `NIL`.
+    pub fn set_parent(
+        &mut self,
+        child: Handle<Object>,
+        parent: Handle<Object>,
+    ) -> Result<(), Error> {
+        if !self.objects.contains(child) {
+            return Err(Error::InvalidObject(child));
+        }
+
+        if !parent.is_nil() && !self.objects.contains(parent) {
+            return Err(Error::InvalidObject(parent));
+        }
+
+        if parent == child || self.ancestors(parent).any(|ancestor| ancestor == child) {
+            return Err(Error::Cycle { child, parent });
+        }
+
+        self.unlink(child);
+
+        self.objects.get_mut(child).unwrap().parent = parent;
+
+        if let Some(parent) = self.objects.get_mut(parent) {
+            parent.children.push(child);
+        }
+
+        self.mark_transform_dirty(child);
+        Ok(())
+    }
+
+    /// Removes the object from the children of its parent.
+    fn unlink(&mut self, child: Handle<Object>) {
+        let parent = self
+            .objects
+            .get(child)
+            .map_or(Handle::NIL, |object| object.parent);
+
+        if let Some(parent) = self.objects.get_mut(parent) {
+            parent.children.retain(|sibling| *sibling != child);
+        }
+    }
+
+    /// Removes an object, its children are moved to its parent.
+    pub fn remove_object(&mut self, handle: Handle<Object>) -> Option<Object> {
+        self.unlink(handle);
+
+        let mut object = self.objects.remove(handle)?;
+        object.components = self.components.remove_all(handle);
+
+        let parent = std::mem::replace(&mut object.parent, Handle::NIL);
+        let children = std::mem::take(&mut object.children);
+
+        if let Some(parent) = self.objects.get_mut(parent) {
+            parent.children.extend(&children);
+        }
+
+        for child in children {
+            if let Some(object) = self.objects.get_mut(child) {
+                object.parent = parent;
+                self.mark_transform_dirty(child);
+            }
+        }
+
+        Some(object)
+    }
+
+    /// Removes an object with all its descendants, returned in depth-first order and unlinked
+    /// from each other. Removes nothing for a `NIL` handle.
+    pub fn remove_subtree(&mut self, handle: Handle<Object>) -> Vec<Object> {
+        if handle.is_nil() {
+            return Vec::new();
+        }
+
+        self.unlink(handle);
+
+        let subtree: Vec<Handle<Object>> = self.depth_first(handle).collect();
+
+        subtree
+            .into_iter()
+            .filter_map(|handle| {
+                let mut object = self.objects.remove(handle)?;
+                object.components = self.components.remove_all(handle);
+                object.parent = Handle::NIL;
+                object.children.clear();
+                Some(object)
+            })
+            .collect()
+    }
+
+    /// The transform relative to the parent, objects without one are at the parent.
+    pub fn transform(&self, handle: Handle<Object>) -> Transform {
+        self.component(handle).copied().unwrap_or_default()
+    }
+
+    pub fn set_transform(&mut self, handle: Handle<Object>, transform: Transform) {
+        let _ = self.insert_component(handle, transform);
+    }
+
+    /// Sets the local transform so that the object ends up at a transform relative to the realm.
+    pub fn set_world_transform(&mut self, handle: Handle<Object>, transform: Transform) {
+        let Some(object) = self.get(handle) else {
+            return;
+        };
+
+        // Computed from the parent chain, its world matrices might not be updated yet.
+        let parent_world = self.compute_world_matrix(object.parent());
+        let local = parent_world.inverse() * transform.matrix();
+
+        self.set_transform(handle, Transform::from_matrix(local));
+    }
+
+    /// Schedules the world matrix of an object and its descendants to be recomputed by
+    /// `update_transforms`. Changing a `Transform` through the realm does this already.
+    pub fn mark_transform_dirty(&mut self, handle: Handle<Object>) {
+        if let Some(object) = self.objects.get_mut(handle)
+            && !object.transform_dirty
+        {
+            object.transform_dirty = true;
+            self.dirty_transforms.push(handle);
+        }
+    }
+
+    /// Recomputes the world matrices of objects whose transform or parent changed and of their
+    /// descendants, call once per frame after moving objects.
+    pub fn update_transforms(&mut self) {
+        for handle in std::mem::take(&mut self.dirty_transforms) {
+            let is_dirty = self
+                .objects
+                .get(handle)
+                .is_some_and(|object| object.transform_dirty);
+
+            // A dirty ancestor updates the whole subtree, including this object.
+            let has_dirty_ancestor = self.ancestors(handle).any(|ancestor| {
+                self.objects
+                    .get(ancestor)
+                    .is_some_and(|object| object.transform_dirty)
+            });
+
+            if !is_dirty || has_dirty_ancestor {
+                continue;
+            }
+
+            let mut stack = vec![(handle, self.parent_world_matrix(handle))];
+
+            while let Some((handle, parent_world)) = stack.pop() {
+                let local = self.transform(handle).matrix();
+
+                let Some(object) = self.objects.get_mut(handle) else {
+                    continue;
+                };
+
+                object.world_matrix = parent_world * local;
+                object.transform_dirty = false;
+
+                let world = object.world_matrix;
+                stack.extend(object.children.iter().map(|child| (*child, world)));
+            }
+        }
+    }
+
+    fn compute_world_matrix(&self, handle: Handle<Object>) -> Mat4 {
+        if !self.objects.contains(handle) {
+            return Mat4::IDENTITY;
+        }
+
+        self.ancestors(handle)
+            .fold(self.transform(handle).matrix(), |world, ancestor| {
+                self.transform(ancestor).matrix() * world
+            })
+    }
+
+    fn parent_world_matrix(&self, handle: Handle<Object>) -> Mat4 {
+        self.get(handle)
+            .and_then(|object| self.get(object.parent))
+            .map_or(Mat4::IDENTITY, |parent| parent.world_matrix)
+    }
+
+    /// Converts a point relative to the object to the realm, as of the last `update_transforms`.
+    pub fn local_to_world(&self, handle: Handle<Object>, point: Vec3) -> Vec3 {
+        self.get(handle)
+            .map_or(point, |object| object.world_matrix.transform_point3(point))
+    }
+
+    /// Converts a point relative to the realm to the object, as of the last `update_transforms`.
+    pub fn world_to_local(&self, handle: Handle<Object>, point: Vec3) -> Vec3 {
+        self.get(handle).map_or(point, |object| {
+            object.world_matrix.inverse().transform_point3(point)
+        })
+    }
+
+    /// The parent of an object, its parent and so on up to the root.
+    pub fn ancestors(&self, handle: Handle<Object>) -> Ancestors<'_> {
+        Ancestors {
+            realm: self,
+            current: handle,
+        }
+    }
+
+    /// The object and its descendants, each parent before its children. A `NIL` handle traverses
+    /// all roots.
+    pub fn depth_first(&self, handle: Handle<Object>) -> DepthFirst<'_> {
+        DepthFirst {
+            realm: self,
+            stack: self.start(handle),
+        }
+    }
+
+    /// The object and its descendants, ordered by depth. A `NIL` handle traverses all roots.
+    pub fn breadth_first(&self, handle: Handle<Object>) -> BreadthFirst<'_> {
+        BreadthFirst {
+            realm: self,
+            queue: self.start(handle).into_iter().rev().collect(),
+        }
+    }
+
+    /// The objects a traversal starts with, reversed to be popped from a stack.
+    fn start(&self, handle: Handle<Object>) -> Vec<Handle<Object>> {
+        if handle.is_nil() {
+            let mut roots: Vec<Handle<Object>> = self.roots().collect();
+            roots.reverse();
+            roots
+        } else if self.objects.contains(handle) {
+            vec![handle]
+        } else {
+            Vec::new()
+        }
+    }
+}
+
+pub struct Ancestors<'a> {
+    realm: &'a Realm,
+    current: Handle<Object>,
+}
+
+impl Iterator for Ancestors<'_> {
+    type Item = Handle<Object>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let parent = self.realm.get(self.current)?.parent;
+        self.current = parent;
+
+        (!parent.is_nil()).then_some(parent)
+    }
+}
+
+pub struct DepthFirst<'a> {
+    realm: &'a Realm,
+    stack: Vec<Handle<Object>>,
+}
+
+impl Iterator for DepthFirst<'_> {
+    type Item = Handle<Object>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let handle = self.stack.pop()?;
+
+        if let Some(object) = self.realm.get(handle) {
+            self.stack.extend(object.children.iter().rev());
         }
+
+        Some(handle)
     }
+}
+
+pub struct BreadthFirst<'a> {
+    realm: &'a Realm,
+    queue: VecDeque<Handle<Object>>,
+}
+
+impl Iterator for BreadthFirst<'_> {
+    type Item = Handle<Object>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let handle = self.queue.pop_front()?;
+
+        if let Some(object) = self.realm.get(handle) {
+            self.queue.extend(&object.children);
+        }
 
-    pub fn add_object(&mut self, object: Object) -> Handle<Object> {
-        // TODO: account for relationship data in object
-        self.objects.insert(object)
+        Some(handle)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::Quat;
+
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    struct Health(u32);
+
+    impl Component for Health {}
+
+    #[derive(Debug, PartialEq)]
+    struct Speed(f32);
+
+    impl Component for Speed {}
+
+    #[test]
+    fn components_are_stored_and_queried_per_type() {
+        let mut realm = Realm::new();
+        let a = realm.add_object(Object::new().with_component(Health(1)));
+        let b = realm.add_object(
+            Object::new()
+                .with_component(Health(2))
+                .with_component(Speed(2.0)),
+        );
+        let c = realm.add_object(Object::new().with_component(Speed(3.0)));
+
+        assert_eq!(realm.column::<Health>().unwrap().owners(), [a, b]);
+        assert!(realm.column::<Transform>().is_none());
+
+        for (_, (health, speed)) in realm.query_mut::<(&mut Health, &Speed)>() {
+            health.0 += speed.0 as u32;
+        }
+        assert_eq!(
+            realm.query::<(&Health, &Speed)>().collect::<Vec<_>>(),
+            [(b, (&Health(4), &Speed(2.0)))]
+        );
+
+        assert_eq!(realm.insert_component(c, Health(7)), Ok(None));
+        assert_eq!(realm.insert_component(c, Health(8)), Ok(Some(Health(7))));
+        assert_eq!(realm.remove_component::<Health>(a), Some(Health(1)));
+        assert_eq!(realm.remove_component::<Health>(a), None);
+        assert_eq!(realm.column::<Health>().unwrap().owners(), [c, b]);
+        assert_eq!(realm.component::<Health>(b), Some(&Health(4)));
+
+        // Removed objects keep their components until they are added again.
+        let removed = realm.remove_object(b).unwrap();
+        assert_eq!(removed.component::<Speed>(), Some(&Speed(2.0)));
+        assert_eq!(realm.column::<Speed>().unwrap().owners(), [c]);
+        assert_eq!(realm.insert_component(b, Speed(1.0)), Err(Speed(1.0)));
+
+        let b = realm.add_object(removed);
+        assert_eq!(realm.component::<Health>(b), Some(&Health(4)));
+        assert_eq!(realm.query::<&Speed>().count(), 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "mutably more than once")]
+    fn queries_cannot_alias_mutable_components() {
+        let mut realm = Realm::new();
+        realm.add_object(Object::new().with_component(Health(1)));
+
+        let _ = realm.query_mut::<(&mut Health, &Health)>().count();
+    }
+
+    #[test]
+    fn objects_are_found_by_name_tag_and_components() {
+        let mut realm = Realm::new();
+        let player = realm.add_object(
+            Object::new()
+                .with_name("player")
+                .with_tag("friendly")
+                .with_component(Health(10)),
+        );
+        let enemy = realm.add_object(
+            Object::new()
+                .with_name("enemy")
+                .with_tag("hostile")
+                .with_component(Health(5)),
+        );
+        let decoy = realm.add_object(Object::new().with_name("enemy").with_tag("hostile"));
+
+        assert_eq!(realm.find("enemy"), Some(enemy));
+        assert_eq!(realm.find("nobody"), None);
+        assert_eq!(realm.tagged("hostile").collect::<Vec<_>>(), [enemy, decoy]);
+
+        let hostile: Vec<_> = realm
+            .query_where::<&Health>(|object| object.has_tag("hostile"))
+            .collect();
+        assert_eq!(hostile, [(enemy, &Health(5))]);
+
+        let object = realm.get_mut(player).unwrap();
+        object.set_name("hero");
+        object.add_tag("hostile");
+        object.add_tag("hostile");
+        assert_eq!(object.tags(), ["friendly", "hostile"]);
+        object.remove_tag("friendly");
+        assert!(!object.has_tag("friendly"));
+
+        assert_eq!(realm.find("hero"), Some(player));
+        assert_eq!(
+            realm.tagged("hostile").collect::<Vec<_>>(),
+            [player, enemy, decoy]
+        );
+    }
+
+    #[test]
+    fn remove_subtree_unlinks_removed_objects() {
+        let mut realm = Realm::new();
+        let root = realm.add_object(Object::new().with_name("root"));
+        let child = realm.add_object(Object::new().with_name("child").with_parent(root));
+        let grandchild = realm.add_object(Object::new().with_parent(child));
+        let other = realm.add_object(Object::new().with_name("other"));
+
+        assert!(realm.remove_subtree(Handle::NIL).is_empty());
+        assert_eq!(realm.objects().count(), 4);
+
+        let removed = realm.remove_subtree(child);
+        assert_eq!(removed.len(), 2);
+        assert_eq!(removed[0].name(), "child");
+
+        for object in &removed {
+            assert!(object.parent().is_nil());
+            assert!(object.children().is_empty());
+        }
+
+        assert!(realm.get(grandchild).is_none());
+        assert!(realm.get(root).unwrap().children().is_empty());
+        assert!(realm.get(other).is_some());
+    }
+
+    #[test]
+    fn reparenting_keeps_the_hierarchy_consistent() {
+        let mut realm = Realm::new();
+        let a = realm.add_object(Object::new());
+        let b = realm.add_object(Object::new().with_parent(a));
+        let c = realm.add_object(Object::new().with_parent(b));
+        let d = realm.add_object(Object::new().with_parent(a));
+        let e = realm.add_object(Object::new());
+
+        assert_eq!(realm.roots().collect::<Vec<_>>(), [a, e]);
+        assert_eq!(realm.ancestors(c).collect::<Vec<_>>(), [b, a]);
+        assert_eq!(
+            realm.depth_first(Handle::NIL).collect::<Vec<_>>(),
+            [a, b, c, d, e]
+        );
+        assert_eq!(
+            realm.breadth_first(Handle::NIL).collect::<Vec<_>>(),
+            [a, e, b, d, c]
+        );
+
+        assert!(matches!(
+            realm.set_parent(a, c),
+            Err(Error::Cycle { child, parent }) if child == a && parent == c
+        ));
+        assert!(matches!(realm.set_parent(b, b), Err(Error::Cycle { .. })));
+
+        realm.set_parent(b, e).unwrap();
+        assert_eq!(realm.get(a).unwrap().children(), [d]);
+        assert_eq!(realm.get(e).unwrap().children(), [b]);
+        assert_eq!(realm.ancestors(c).collect::<Vec<_>>(), [b, e]);
+
+        // Removing an object moves its children to its parent.
+        let removed = realm.remove_object(b).unwrap();
+        assert!(removed.parent().is_nil());
+        assert!(removed.children().is_empty());
+        assert_eq!(realm.get(c).unwrap().parent(), e);
+        assert_eq!(realm.get(e).unwrap().children(), [c]);
+
+        assert!(matches!(
+            realm.set_parent(b, a),
+            Err(Error::InvalidObject(handle)) if handle == b
+        ));
+        realm.set_parent(c, Handle::NIL).unwrap();
+        assert_eq!(realm.roots().collect::<Vec<_>>(), [a, c, e]);
+        assert!(realm.get(e).unwrap().children().is_empty());
+    }
+
+    #[test]
+    fn world_transforms_follow_their_parents() {
+        let mut realm = Realm::new();
+        let parent = realm.add_object(Object::new().with_transform(Transform {
+            translation: Vec3::new(10.0, 0.0, 0.0),
+            rotation: Quat::from_rotation_z(std::f32::consts::FRAC_PI_2),
+            scale: Vec3::splat(2.0),
+        }));
+        let child = realm.add_object(
+            Object::new()
+                .with_parent(parent)
+                .with_transform(Transform::from_translation(Vec3::X)),
+        );
+        let grandchild = realm.add_object(Object::new().with_parent(child));
+
+        // Nothing moves before the update.
+        assert_eq!(realm.local_to_world(child, Vec3::ZERO), Vec3::ZERO);
+
+        realm.update_transforms();
+        let close = |a: Vec3, b: Vec3| a.abs_diff_eq(b, 1e-5);
+        assert!(close(
+            realm.local_to_world(child, Vec3::ZERO),
+            Vec3::new(10.0, 2.0, 0.0)
+        ));
+        assert!(close(
+            realm.local_to_world(grandchild, Vec3::X),
+            Vec3::new(10.0, 4.0, 0.0)
+        ));
+        assert!(close(
+            realm.world_to_local(child, Vec3::new(10.0, 4.0, 0.0)),
+            Vec3::X
+        ));
+
+        // Keeps the world position when the parent moved but wasn't updated yet.
+        realm.set_transform(parent, Transform::from_translation(Vec3::Y));
+        realm.set_world_transform(child, Transform::from_translation(Vec3::new(1.0, 1.0, 0.0)));
+        assert!(close(realm.transform(child).translation, Vec3::X));
+
+        realm.update_transforms();
+        assert!(close(
+            realm.local_to_world(grandchild, Vec3::ZERO),
+            Vec3::new(1.0, 1.0, 0.0)
+        ));
+
+        realm.set_parent(child, Handle::NIL).unwrap();
+        realm.update_transforms();
+        assert!(close(realm.local_to_world(grandchild, Vec3::ZERO), Vec3::X));
+    }
+
+    #[test]
+    fn readded_objects_update_their_transform() {
+        let mut realm = Realm::new();
+        let transform = Transform::from_translation(Vec3::X);
+
+        // Removed before its first update, while still dirty.
+        let handle = realm.add_object(Object::new().with_transform(transform));
+        let object = realm.remove_object(handle).unwrap();
+
+        let handle = realm.add_object(object);
+        realm.update_transforms();
+
+        assert_eq!(realm.local_to_world(handle, Vec3::ZERO), Vec3::X);
+    }
+
+    #[test]
+    fn mutable_transform_access_marks_it_dirty() {
+        let mut realm = Realm::new();
+        let parent = realm.add_object(Object::new().with_transform(Transform::default()));
+        let child = realm.add_object(
+            Object::new()
+                .with_parent(parent)
+                .with_transform(Transform::default()),
+        );
+        realm.update_transforms();
+
+        realm
+            .component_mut::<Transform>(parent)
+            .unwrap()
+            .translation = Vec3::X;
+        realm.update_transforms();
+        assert_eq!(realm.local_to_world(child, Vec3::ZERO), Vec3::X);
+
+        for (_, transform) in realm.query_mut::<&mut Transform>() {
+            transform.translation += Vec3::Y;
+        }
+        realm.update_transforms();
+        assert_eq!(
+            realm.local_to_world(child, Vec3::ZERO),
+            Vec3::X + Vec3::Y * 2.0
+        );
     }
 }
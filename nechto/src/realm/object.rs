@@ -1,37 +1,141 @@
+use std::any::Any;
+
+use glam::Mat4;
+
 use crate::handle::Handle;
+use crate::realm::component::AnyComponent;
+use crate::realm::{Component, Transform};
 
 pub struct Object {
-    data: Box<dyn ObjectData>,
-    parent: Handle<Object>,
-    children: Vec<Handle<Object>>,
-}
-
-pub trait ObjectData: 'static {
-    fn name(&self) -> &'static str;
+    /// Components of an object outside a realm, a realm stores them in its columns.
+    pub(super) components: Vec<Box<dyn AnyComponent>>,
+    name: String,
+    tags: Vec<String>,
+    pub(super) parent: Handle<Object>,
+    pub(super) children: Vec<Handle<Object>>,
+    pub(super) world_matrix: Mat4,
+    /// The world matrix of the object and its descendants needs to be recomputed.
+    pub(super) transform_dirty: bool,
 }
 
 impl Object {
-    pub fn new(data: impl ObjectData) -> Self {
+    pub fn new() -> Self {
         Object {
-            data: Box::new(data),
+            components: Vec::new(),
+            name: String::new(),
+            tags: Vec::new(),
             parent: Handle::NIL,
             children: Vec::new(),
+            world_matrix: Mat4::IDENTITY,
+            transform_dirty: false,
         }
     }
 
+    /// Links the object into the hierarchy when it is added to a realm.
     pub fn with_parent(mut self, parent: Handle<Object>) -> Self {
         self.parent = parent;
         self
     }
 
+    /// Moves the child under the object when it is added to a realm.
     pub fn with_child(mut self, child: Handle<Object>) -> Self {
         self.children.push(child);
         self
     }
+
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    pub fn with_tag(mut self, tag: impl Into<String>) -> Self {
+        self.add_tag(tag);
+        self
+    }
+
+    /// Adds or replaces a component. Components of an object in a realm are inserted with
+    /// `Realm::insert_component`, which is why objects have no setter for them.
+    pub fn with_component<T: Component>(mut self, component: T) -> Self {
+        match self.component_mut::<T>() {
+            Some(own) => *own = component,
+            None => self.components.push(Box::new(component)),
+        }
+
+        self
+    }
+
+    pub fn with_transform(self, transform: Transform) -> Self {
+        self.with_component(transform)
+    }
+
+    pub fn parent(&self) -> Handle<Object> {
+        self.parent
+    }
+
+    pub fn children(&self) -> &[Handle<Object>] {
+        &self.children
+    }
+
+    /// The transform relative to the realm, as of the last `Realm::update_transforms`.
+    pub fn world_matrix(&self) -> Mat4 {
+        self.world_matrix
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn set_name(&mut self, name: impl Into<String>) {
+        self.name = name.into();
+    }
+
+    pub fn tags(&self) -> &[String] {
+        &self.tags
+    }
+
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|own| own == tag)
+    }
+
+    pub fn add_tag(&mut self, tag: impl Into<String>) {
+        let tag = tag.into();
+
+        if !self.has_tag(&tag) {
+            self.tags.push(tag);
+        }
+    }
+
+    pub fn remove_tag(&mut self, tag: &str) {
+        self.tags.retain(|own| own != tag);
+    }
+
+    /// Components of an object that is not in a realm, like one returned by
+    /// `Realm::remove_object`. Components of objects in a realm are accessed through the realm.
+    pub fn component<T: Component>(&self) -> Option<&T> {
+        self.components
+            .iter()
+            .find_map(|component| (component.as_ref() as &dyn Any).downcast_ref())
+    }
+
+    pub fn component_mut<T: Component>(&mut self) -> Option<&mut T> {
+        self.components
+            .iter_mut()
+            .find_map(|component| (component.as_mut() as &mut dyn Any).downcast_mut())
+    }
+
+    pub fn remove_component<T: Component>(&mut self) -> Option<T> {
+        let index = self
+            .components
+            .iter()
+            .position(|component| (component.as_ref() as &dyn Any).is::<T>())?;
+
+        let component = self.components.swap_remove(index) as Box<dyn Any>;
+        component.downcast().ok().map(|component| *component)
+    }
 }
 
-pub trait IntoObject: ObjectData + Sized + 'static {
+pub trait IntoObject: Component + Sized {
     fn into_object(self) -> Object {
-        Object::new(self)
+        Object::new().with_component(self)
     }
 }
@@ -0,0 +1,727 @@
+use std::any::TypeId;
+use std::fmt;
+use std::str::FromStr;
+
+use glam::{Quat, Vec3};
+
+use crate::handle::{Handle, LoadIds, SaveIds, StableId, StableIdError};
+use crate::realm::{Component, Object, Realm, Transform};
+use crate::vfs::{self, IntoPathSpec, VirtualFs};
+
+/// Version of the scene format written by `Scene`, scenes of older versions are upgraded when
+/// they are parsed.
+pub const SCENE_VERSION: u32 = UPGRADES.len() as u32 + 1;
+
+/// Upgrades of scenes of each older version to the next one, starting with version 1.
+const UPGRADES: &[fn(&mut Scene)] = &[];
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("line {line}: invalid scene line")]
+    InvalidLine { line: usize },
+
+    #[error("scene has no version")]
+    MissingVersion,
+
+    #[error("scene version {0} is newer than the supported version {SCENE_VERSION}")]
+    UnsupportedVersion(u32),
+
+    #[error("object {object}: unknown component {component}")]
+    UnknownComponent { object: StableId, component: String },
+
+    #[error("object {object}: component {component} is saved twice")]
+    DuplicateComponent { object: StableId, component: String },
+
+    #[error("object {object}: component {component}: {source}")]
+    InvalidComponent {
+        object: StableId,
+        component: String,
+        source: FieldError,
+    },
+
+    #[error(transparent)]
+    StableId(#[from] StableIdError),
+
+    #[error(transparent)]
+    Realm(#[from] super::Error),
+
+    #[error("scene is not valid UTF-8")]
+    InvalidUtf8,
+
+    #[error(transparent)]
+    Vfs(#[from] vfs::Error),
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum FieldError {
+    #[error("missing field {0}")]
+    Missing(String),
+
+    #[error("invalid value of field {0}")]
+    Invalid(String),
+}
+
+/// Fields of a saved component as `key = value` pairs, in the order they were set.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Fields {
+    fields: Vec<(String, String)>,
+}
+
+impl Fields {
+    pub fn new() -> Self {
+        Self { fields: Vec::new() }
+    }
+
+    /// Sets a field to the text of the value. Keys are single words without `=`.
+    pub fn set(&mut self, key: &str, value: impl fmt::Display) {
+        assert!(is_word(key), "invalid scene field key {:?}", key);
+
+        let value = value.to_string();
+
+        match self.fields.iter_mut().find(|(own, _)| own == key) {
+            Some((_, own)) => *own = value,
+            None => self.fields.push((key.to_owned(), value)),
+        }
+    }
+
+    pub fn set_vec3(&mut self, key: &str, value: Vec3) {
+        self.set(key, format_args!("{} {} {}", value.x, value.y, value.z));
+    }
+
+    pub fn set_quat(&mut self, key: &str, value: Quat) {
+        self.set(
+            key,
+            format_args!("{} {} {} {}", value.x, value.y, value.z, value.w),
+        );
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.fields
+            .iter()
+            .find(|(own, _)| own == key)
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.fields
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+    }
+
+    fn required(&self, key: &str) -> Result<&str, FieldError> {
+        self.get(key)
+            .ok_or_else(|| FieldError::Missing(key.to_owned()))
+    }
+
+    pub fn parse<T: FromStr>(&self, key: &str) -> Result<T, FieldError> {
+        self.required(key)?
+            .parse()
+            .map_err(|_| FieldError::Invalid(key.to_owned()))
+    }
+
+    /// Parses a field, or returns the default if the scene was saved without it.
+    pub fn parse_or<T: FromStr>(&self, key: &str, default: T) -> Result<T, FieldError> {
+        match self.get(key) {
+            Some(_) => self.parse(key),
+            None => Ok(default),
+        }
+    }
+
+    fn floats<const N: usize>(&self, key: &str) -> Result<[f32; N], FieldError> {
+        let invalid = || FieldError::Invalid(key.to_owned());
+        let mut words = self.required(key)?.split_whitespace();
+        let mut values = [0.0; N];
+
+        for value in &mut values {
+            *value = words
+                .next()
+                .ok_or_else(invalid)?
+                .parse()
+                .map_err(|_| invalid())?;
+        }
+
+        match words.next() {
+            Some(_) => Err(invalid()),
+            None => Ok(values),
+        }
+    }
+
+    pub fn vec3(&self, key: &str) -> Result<Vec3, FieldError> {
+        self.floats(key).map(Vec3::from_array)
+    }
+
+    pub fn quat(&self, key: &str) -> Result<Quat, FieldError> {
+        self.floats(key).map(Quat::from_array)
+    }
+
+    /// A reference to an asset, stored as a VFS path spec like `$data/models/cube.ply`.
+    pub fn path(&self, key: &str) -> Result<&str, FieldError> {
+        let path = self.required(key)?;
+
+        path.as_path_spec()
+            .map_err(|_| FieldError::Invalid(key.to_owned()))?;
+
+        Ok(path)
+    }
+}
+
+/// A component that can be saved in a scene.
+pub trait SceneComponent: Component + Sized {
+    /// Name of the component in scene files, which must not change once scenes are saved.
+    const NAME: &'static str;
+
+    fn save(&self, fields: &mut Fields);
+    fn load(fields: &Fields) -> Result<Self, FieldError>;
+}
+
+impl SceneComponent for Transform {
+    const NAME: &'static str = "transform";
+
+    fn save(&self, fields: &mut Fields) {
+        fields.set_vec3("translation", self.translation);
+        fields.set_quat("rotation", self.rotation);
+        fields.set_vec3("scale", self.scale);
+    }
+
+    fn load(fields: &Fields) -> Result<Self, FieldError> {
+        Ok(Transform {
+            translation: fields.vec3("translation")?,
+            rotation: fields.quat("rotation")?,
+            scale: fields.vec3("scale")?,
+        })
+    }
+}
+
+struct Registration {
+    name: &'static str,
+    type_id: TypeId,
+    save: fn(&Realm, Handle<Object>) -> Option<Fields>,
+    insert: fn(&mut Realm, Handle<Object>, &Fields) -> Result<(), FieldError>,
+}
+
+/// Component types saved in and loaded from scenes, `Transform` is always registered.
+pub struct SceneComponents {
+    registrations: Vec<Registration>,
+}
+
+impl SceneComponents {
+    pub fn new() -> Self {
+        let mut components = Self {
+            registrations: Vec::new(),
+        };
+
+        components.register::<Transform>();
+        components
+    }
+
+    pub fn register<T: SceneComponent>(&mut self) {
+        if self
+            .registrations
+            .iter()
+            .any(|own| own.type_id == TypeId::of::<T>())
+        {
+            return;
+        }
+
+        assert!(
+            is_word(T::NAME),
+            "invalid scene component name {:?}",
+            T::NAME
+        );
+        assert!(
+            self.registration(T::NAME).is_none(),
+            "scene component name {} is registered twice",
+            T::NAME
+        );
+
+        self.registrations.push(Registration {
+            name: T::NAME,
+            type_id: TypeId::of::<T>(),
+            save: save_component::<T>,
+            insert: insert_component::<T>,
+        });
+    }
+
+    fn registration(&self, name: &str) -> Option<&Registration> {
+        self.registrations.iter().find(|own| own.name == name)
+    }
+}
+
+fn save_component<T: SceneComponent>(realm: &Realm, handle: Handle<Object>) -> Option<Fields> {
+    let mut fields = Fields::new();
+    realm.component::<T>(handle)?.save(&mut fields);
+
+    Some(fields)
+}
+
+fn insert_component<T: SceneComponent>(
+    realm: &mut Realm,
+    handle: Handle<Object>,
+    fields: &Fields,
+) -> Result<(), FieldError> {
+    let replaced = realm.insert_component(handle, T::load(fields)?);
+    debug_assert!(
+        matches!(replaced, Ok(None)),
+        "duplicate components are rejected by to_realm"
+    );
+
+    Ok(())
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SceneObject {
+    pub id: StableId,
+    pub name: String,
+    /// `StableId::NIL` for roots.
+    pub parent: StableId,
+    pub tags: Vec<String>,
+    /// Saved components by their `SceneComponent::NAME`.
+    pub components: Vec<(String, Fields)>,
+}
+
+/// Saved objects of a realm with their hierarchy and components, stored as sections of
+/// `key = value` lines:
+///
+/// ```text
+/// version = 1
+///
+/// [object 1]
+/// name = camera
+/// tags = main
+///
+/// [object 1 transform]
+/// translation = 0 1 5
+/// rotation = 0 0 0 1
+/// scale = 1 1 1
+///
+/// [object 2]
+/// parent = 1
+/// ```
+///
+/// Parents are saved before their children. Components of unregistered types are not saved.
+///
+/// Values escape backslashes as `\\`, line breaks and tabs as `\n`, `\r` and `\t`, and spaces at
+/// their start or end as `\s`. Commas in tags are escaped as `\,`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Scene {
+    pub version: u32,
+    pub objects: Vec<SceneObject>,
+}
+
+impl Scene {
+    pub fn new() -> Self {
+        Self {
+            version: SCENE_VERSION,
+            objects: Vec::new(),
+        }
+    }
+
+    pub fn from_realm(realm: &Realm, components: &SceneComponents) -> Result<Self, Error> {
+        let handles: Vec<Handle<Object>> = realm.depth_first(Handle::NIL).collect();
+        let ids = SaveIds::new(handles.iter().copied());
+        let mut scene = Scene::new();
+
+        for handle in handles {
+            let object = realm.get(handle).unwrap();
+
+            scene.objects.push(SceneObject {
+                id: ids.id(handle)?,
+                name: object.name().to_owned(),
+                parent: ids.id(object.parent())?,
+                tags: object.tags().to_vec(),
+                components: components
+                    .registrations
+                    .iter()
+                    .filter_map(|registration| {
+                        let fields = (registration.save)(realm, handle)?;
+                        Some((registration.name.to_owned(), fields))
+                    })
+                    .collect(),
+            });
+        }
+
+        Ok(scene)
+    }
+
+    /// Creates a realm with the objects of the scene. World matrices are computed by the next
+    /// `Realm::update_transforms`.
+    pub fn to_realm(&self, components: &SceneComponents) -> Result<Realm, Error> {
+        let mut realm = Realm::new();
+        let mut ids = LoadIds::new();
+
+        for saved in &self.objects {
+            let mut object = Object::new().with_name(&saved.name);
+
+            for tag in &saved.tags {
+                object.add_tag(tag);
+            }
+
+            let handle = realm.add_object(object);
+            ids.insert(saved.id, handle)?;
+
+            for (index, (name, fields)) in saved.components.iter().enumerate() {
+                if saved.components[..index]
+                    .iter()
+                    .any(|(other, _)| other == name)
+                {
+                    return Err(Error::DuplicateComponent {
+                        object: saved.id,
+                        component: name.clone(),
+                    });
+                }
+
+                let registration =
+                    components
+                        .registration(name)
+                        .ok_or_else(|| Error::UnknownComponent {
+                            object: saved.id,
+                            component: name.clone(),
+                        })?;
+
+                (registration.insert)(&mut realm, handle, fields).map_err(|source| {
+                    Error::InvalidComponent {
+                        object: saved.id,
+                        component: name.clone(),
+                        source,
+                    }
+                })?;
+            }
+        }
+
+        for saved in &self.objects {
+            if saved.parent != StableId::NIL {
+                realm.set_parent(ids.handle(saved.id)?, ids.handle(saved.parent)?)?;
+            }
+        }
+
+        Ok(realm)
+    }
+
+    /// Parses a scene and upgrades it to `SCENE_VERSION`.
+    pub fn parse(text: &str) -> Result<Self, Error> {
+        let mut version = None;
+        let mut objects: Vec<SceneObject> = Vec::new();
+        let mut section = Section::Version;
+
+        for (index, line) in text.lines().enumerate() {
+            let line = line.trim();
+
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let invalid = || Error::InvalidLine { line: index + 1 };
+
+            if let Some(header) = line.strip_prefix('[') {
+                let header = header.strip_suffix(']').ok_or_else(invalid)?;
+                let mut words = header.split_whitespace();
+
+                if words.next() != Some("object") {
+                    return Err(invalid());
+                }
+
+                let id: StableId = words
+                    .next()
+                    .and_then(|id| id.parse().ok())
+                    .ok_or_else(invalid)?;
+
+                match (words.next(), words.next()) {
+                    (None, _) => {
+                        if id == StableId::NIL {
+                            return Err(invalid());
+                        }
+
+                        objects.push(SceneObject {
+                            id,
+                            name: String::new(),
+                            parent: StableId::NIL,
+                            tags: Vec::new(),
+                            components: Vec::new(),
+                        });
+                        section = Section::Object(Vec::new());
+                    }
+                    // Components follow the object they belong to, once each.
+                    (Some(component), None) => {
+                        let object = objects
+                            .last_mut()
+                            .filter(|object| object.id == id)
+                            .filter(|object| {
+                                !object.components.iter().any(|(name, _)| name == component)
+                            })
+                            .ok_or_else(invalid)?;
+
+                        object
+                            .components
+                            .push((component.to_owned(), Fields::new()));
+                        section = Section::Component(object.components.len() - 1);
+                    }
+                    _ => return Err(invalid()),
+                }
+
+                continue;
+            }
+
+            let (key, value) = line.split_once('=').ok_or_else(invalid)?;
+            let (key, value) = (key.trim(), value.trim());
+
+            match &mut section {
+                Section::Version if key == "version" && version.is_none() => {
+                    version = Some(value.parse().map_err(|_| invalid())?);
+                }
+                Section::Version => return Err(invalid()),
+                Section::Object(keys) => {
+                    let object = objects.last_mut().unwrap();
+
+                    if keys.contains(&key) {
+                        return Err(invalid());
+                    }
+
+                    keys.push(key);
+
+                    match key {
+                        "name" => object.name = unescape(value).ok_or_else(invalid)?,
+                        "parent" => object.parent = value.parse().map_err(|_| invalid())?,
+                        "tags" => {
+                            object.tags = split_tags(value)
+                                .filter(|tag| !tag.is_empty())
+                                .map(|tag| unescape(tag).ok_or_else(invalid))
+                                .collect::<Result<_, _>>()?
+                        }
+                        _ => return Err(invalid()),
+                    }
+                }
+                Section::Component(component) => {
+                    let fields = &mut objects.last_mut().unwrap().components[*component].1;
+
+                    if !is_word(key) || fields.get(key).is_some() {
+                        return Err(invalid());
+                    }
+
+                    fields.set(key, unescape(value).ok_or_else(invalid)?);
+                }
+            }
+        }
+
+        let mut scene = Scene {
+            version: version.ok_or(Error::MissingVersion)?,
+            objects,
+        };
+
+        if scene.version == 0 || scene.version > SCENE_VERSION {
+            return Err(Error::UnsupportedVersion(scene.version));
+        }
+
+        for upgrade in &UPGRADES[scene.version as usize - 1..] {
+            upgrade(&mut scene);
+            scene.version += 1;
+        }
+
+        Ok(scene)
+    }
+
+    pub fn load(vfs: &VirtualFs, path: impl IntoPathSpec) -> Result<Self, Error> {
+        let data = vfs.read(path)?;
+        let text = String::from_utf8(data).map_err(|_| Error::InvalidUtf8)?;
+
+        Self::parse(&text)
+    }
+
+    pub fn save(&self, vfs: &VirtualFs, path: impl IntoPathSpec) -> Result<(), Error> {
+        vfs.write(path, self.to_string())?;
+
+        Ok(())
+    }
+}
+
+/// Section of a scene file the parsed lines belong to.
+enum Section<'a> {
+    /// Lines before the first object.
+    Version,
+    /// An object, with the keys parsed so far.
+    Object(Vec<&'a str>),
+    /// A component of the last object, by its index.
+    Component(usize),
+}
+
+impl fmt::Display for Scene {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "version = {}", self.version)?;
+
+        for object in &self.objects {
+            writeln!(f)?;
+            writeln!(f, "[object {}]", object.id)?;
+
+            if !object.name.is_empty() {
+                writeln!(f, "name = {}", escape(&object.name, false))?;
+            }
+
+            if object.parent != StableId::NIL {
+                writeln!(f, "parent = {}", object.parent)?;
+            }
+
+            if !object.tags.is_empty() {
+                let tags: Vec<String> = object.tags.iter().map(|tag| escape(tag, true)).collect();
+                writeln!(f, "tags = {}", tags.join(", "))?;
+            }
+
+            for (name, fields) in &object.components {
+                writeln!(f)?;
+                writeln!(f, "[object {} {}]", object.id, name)?;
+
+                for (key, value) in fields.iter() {
+                    writeln!(f, "{} = {}", key, escape(value, false))?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Whether text can be a field key or component name.
+fn is_word(text: &str) -> bool {
+    !text.is_empty() && !text.contains(|c: char| c.is_whitespace() || "=[]#".contains(c))
+}
+
+/// Escapes a value so that it stays on its line and keeps surrounding spaces, and commas if it
+/// is a tag.
+fn escape(value: &str, is_tag: bool) -> String {
+    let inner = value.trim_matches(' ');
+    let start = value.len() - value.trim_start_matches(' ').len();
+    let end = value.len() - value.trim_end_matches(' ').len();
+
+    let mut escaped = "\\s".repeat(start);
+
+    // A value of only spaces is all start.
+    if inner.is_empty() {
+        return escaped;
+    }
+
+    for c in inner.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            ',' if is_tag => escaped.push_str("\\,"),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped.push_str(&"\\s".repeat(end));
+    escaped
+}
+
+fn unescape(value: &str) -> Option<String> {
+    let mut unescaped = String::new();
+    let mut chars = value.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+
+        unescaped.push(match chars.next()? {
+            '\\' => '\\',
+            'n' => '\n',
+            'r' => '\r',
+            't' => '\t',
+            's' => ' ',
+            ',' => ',',
+            _ => return None,
+        });
+    }
+
+    Some(unescaped)
+}
+
+/// Splits tags at commas that are not escaped, the tags are still escaped.
+fn split_tags(value: &str) -> impl Iterator<Item = &str> {
+    let mut escaped = false;
+
+    value
+        .split(move |c| {
+            let split = c == ',' && !escaped;
+            escaped = c == '\\' && !escaped;
+            split
+        })
+        .map(str::trim)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    struct Label(String);
+
+    impl Component for Label {}
+
+    impl SceneComponent for Label {
+        const NAME: &'static str = "label";
+
+        fn save(&self, fields: &mut Fields) {
+            fields.set("text", &self.0);
+        }
+
+        fn load(fields: &Fields) -> Result<Self, FieldError> {
+            Ok(Label(fields.required("text")?.to_owned()))
+        }
+    }
+
+    #[test]
+    fn scenes_round_trip() {
+        let mut components = SceneComponents::new();
+        components.register::<Label>();
+
+        let mut realm = Realm::new();
+        let root = realm.add_object(
+            Object::new()
+                .with_name(" two\nlines ")
+                .with_tag("a, b")
+                .with_tag("back\\slash ")
+                .with_transform(Transform::from_translation(Vec3::new(1.0, 2.0, 3.0))),
+        );
+        realm.add_object(
+            Object::new()
+                .with_parent(root)
+                .with_name("[object 9]")
+                .with_component(Label("  \t=  ".to_owned())),
+        );
+
+        let scene = Scene::from_realm(&realm, &components).unwrap();
+        let text = scene.to_string();
+        assert_eq!(Scene::parse(&text).unwrap(), scene);
+
+        let loaded = scene.to_realm(&components).unwrap();
+        let root = loaded.find(" two\nlines ").unwrap();
+        assert_eq!(loaded.get(root).unwrap().tags(), ["a, b", "back\\slash "]);
+        assert_eq!(loaded.transform(root).translation, Vec3::new(1.0, 2.0, 3.0));
+
+        let child = loaded.find("[object 9]").unwrap();
+        assert_eq!(loaded.get(child).unwrap().parent(), root);
+        assert_eq!(loaded.component(child), Some(&Label("  \t=  ".to_owned())));
+    }
+
+    #[test]
+    fn invalid_scenes_are_rejected() {
+        let future = format!("version = {}", SCENE_VERSION + 1);
+        assert!(matches!(
+            Scene::parse(&future),
+            Err(Error::UnsupportedVersion(_))
+        ));
+
+        for text in [
+            "version = 1\n[object 1]\nname = a\nname = b",
+            "version = 1\n[object 1]\n[object 1 label]\ntext = a\ntext = b",
+            "version = 1\n[object 1]\n[object 1 label]\n[object 1 label]",
+            "version = 1\n[object 1]\nname = \\x",
+        ] {
+            assert!(
+                matches!(Scene::parse(text), Err(Error::InvalidLine { .. })),
+                "{text}"
+            );
+        }
+    }
+}
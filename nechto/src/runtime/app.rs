@@ -1,7 +1,10 @@
 use crate::runtime::Resources;
+use crate::vfs::VirtualFs;
 
 #[allow(unused_variables)]
 pub trait App: 'static {
+    /// Called before the VFS is shared, to add search paths or change their permissions.
+    fn configure_vfs(&mut self, vfs: &mut VirtualFs) {}
     fn init(&mut self, resources: &mut Resources) {}
     fn update(&mut self, resources: &mut Resources) {}
 }
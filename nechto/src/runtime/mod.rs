@@ -3,26 +3,39 @@ mod app;
 pub use self::app::App;
 
 use std::sync::Arc;
+use std::time::Duration;
 
+use tracing::{error, info, warn};
 use winit::application::ApplicationHandler;
 use winit::dpi::PhysicalSize;
-use winit::event::WindowEvent;
+use winit::event::{DeviceEvent, DeviceId, WindowEvent};
 use winit::event_loop::{ActiveEventLoop, EventLoop};
-use winit::keyboard::KeyCode;
-use winit::window::{Window, WindowId};
+use winit::window::{CursorGrabMode, Window, WindowId};
 
+use crate::collections::FrameArenas;
 use crate::config::Config;
-use crate::input::{Action, InputHandler};
+use crate::event::{ActionPerformed, ActionPressed, ActionReleased, EventBus, WindowResized};
+use crate::input::{ActionPhase, CursorMode, InputHandler, InputMap, Recording};
 use crate::js;
 use crate::render::Renderer;
-use crate::vfs::VirtualFs;
+use crate::vfs::{Permissions, VirtualFs};
+
+/// Input map with the user's rebindings, saved by the app after changing bindings.
+pub const USER_INPUT_MAP: &str = "$user/input.ini";
+
+/// Input map used until the user changes bindings.
+pub const DEFAULT_INPUT_MAP: &str = "$data/engine/input.ini";
 
 pub struct Resources {
     pub renderer: Option<Renderer>,
     pub config: Config,
     pub input_handler: InputHandler,
+    pub events: EventBus,
     pub vfs: Arc<VirtualFs>,
-    pub js_ctx: js::Context,
+    pub scripts: js::Contexts,
+    /// Ends a frame after each render, including renders requested by the window between
+    /// updates.
+    pub frame_arenas: FrameArenas,
 }
 
 pub struct EventHandler {
@@ -45,6 +58,13 @@ impl EventHandler {
                 event_loop.exit();
             }
             WindowEvent::Resized(size) => {
+                resources.events.emit(WindowResized {
+                    width: size.width,
+                    height: size.height,
+                });
+
+                resources.input_handler.set_window_size(size);
+
                 if let Some(renderer) = &mut resources.renderer {
                     renderer.resize(size);
                     renderer.window().request_redraw();
@@ -53,6 +73,21 @@ impl EventHandler {
             WindowEvent::KeyboardInput { event, .. } => {
                 resources.input_handler.submit_key_event(event);
             }
+            WindowEvent::Ime(ime) => {
+                resources.input_handler.submit_ime(ime);
+            }
+            WindowEvent::MouseInput { state, button, .. } => {
+                resources.input_handler.submit_mouse_button(button, state);
+            }
+            WindowEvent::CursorMoved { position, .. } => {
+                resources.input_handler.submit_cursor_moved(position);
+            }
+            WindowEvent::CursorLeft { .. } => {
+                resources.input_handler.submit_cursor_left();
+            }
+            WindowEvent::MouseWheel { delta, .. } => {
+                resources.input_handler.submit_mouse_wheel(delta);
+            }
             WindowEvent::RedrawRequested => {
                 self.on_render(resources);
             }
@@ -60,21 +95,79 @@ impl EventHandler {
         }
     }
 
+    fn on_device_event(&mut self, resources: &mut Resources, event: DeviceEvent) {
+        match event {
+            DeviceEvent::MouseMotion { delta } => {
+                resources.input_handler.submit_mouse_motion(delta);
+            }
+            _ => {}
+        }
+    }
+
     fn on_update(&mut self, event_loop: &ActiveEventLoop, resources: &mut Resources) {
-        for action in resources.input_handler.actions() {
-            if action.name() == "quit" {
+        let input_handler = &mut resources.input_handler;
+        let events = &mut resources.events;
+
+        input_handler.poll_backend();
+        input_handler.update();
+
+        for action in input_handler.just_pressed_actions() {
+            events.emit(ActionPressed {
+                action: action.name().to_owned(),
+            });
+        }
+
+        for action in input_handler.just_released_actions() {
+            events.emit(ActionReleased {
+                action: action.name().to_owned(),
+            });
+        }
+
+        for event in input_handler.action_events() {
+            if event.phase != ActionPhase::Performed {
+                continue;
+            }
+
+            if event.action.name() == "quit" {
                 event_loop.exit();
             }
+
+            events.emit(ActionPerformed {
+                action: event.action.name().to_owned(),
+            });
         }
 
-        resources.input_handler.reset();
+        for event in resources.scripts.take_emitted_events() {
+            resources.events.emit_raw(event.name, event.payload);
+        }
+
+        resources.events.swap();
+        resources.scripts.dispatch_events(resources.events.events());
+        resources.scripts.update();
+
         self.app.update(resources);
+
+        if let Some(mode) = resources.input_handler.take_cursor_mode_change()
+            && let Some(renderer) = &resources.renderer
+        {
+            apply_cursor_mode(renderer.window(), mode);
+        }
+
+        if let Some(enabled) = resources.input_handler.take_text_mode_change()
+            && let Some(renderer) = &resources.renderer
+        {
+            renderer.window().set_ime_allowed(enabled);
+        }
+
+        resources.input_handler.reset();
     }
 
     fn on_render(&mut self, resources: &mut Resources) {
         if let Some(renderer) = &mut resources.renderer {
             renderer.render();
         }
+
+        resources.frame_arenas.end_frame();
     }
 }
 
@@ -85,26 +178,57 @@ pub struct Runtime {
 
 impl Runtime {
     pub fn new(mut app: impl App) -> Self {
-        let mut input_handler = InputHandler::new();
-
-        input_handler.add_action(KeyCode::Escape, Action::new("quit"));
-
         let mut vfs = VirtualFs::new();
         vfs.add_search_path("$build", "build".into());
         vfs.add_search_path("$data", "data".into());
+        vfs.add_search_path_with_permissions("$user", "user".into(), Permissions::READ_WRITE);
+
+        app.configure_vfs(&mut vfs);
 
         let vfs = Arc::new(vfs);
 
         let config = Config::parse_file("config.ini");
 
-        let js_ctx = js::Context::new(Arc::clone(&vfs));
+        let mut input_handler = InputHandler::new();
+        input_handler.set_input_map(load_input_map(&vfs));
+
+        if !config.input.replay.is_empty() {
+            match Recording::load(&vfs, config.input.replay.as_str()) {
+                Ok(recording) => input_handler.start_replay(recording),
+                Err(err) => error!(
+                    "unable to load input recording {}: {}",
+                    config.input.replay, err
+                ),
+            }
+        }
+
+        if !config.input.record.is_empty() {
+            input_handler.start_recording();
+        }
+
+        let mut scripts = js::Contexts::new(Arc::clone(&vfs));
+        let created = scripts.create(js::ContextOptions {
+            profiler: config.script.profile.then(|| js::ProfilerOptions {
+                sample_interval: match config.script.profile_sample_interval {
+                    0 => None,
+                    millis => Some(Duration::from_millis(millis.into())),
+                },
+            }),
+            ..Default::default()
+        });
+
+        if let Err(err) = created {
+            error!("unable to create script context: {}", err);
+        }
 
         let mut resources = Resources {
             renderer: None,
             config,
             input_handler,
+            events: EventBus::new(),
             vfs,
-            js_ctx,
+            scripts,
+            frame_arenas: FrameArenas::new(),
         };
 
         app.init(&mut resources);
@@ -131,6 +255,12 @@ impl ApplicationHandler for Runtime {
 
         let window = event_loop.create_window(window_attributes).unwrap();
 
+        self.resources
+            .input_handler
+            .set_window_size(window.inner_size());
+        apply_cursor_mode(&window, self.resources.input_handler.cursor_mode());
+        window.set_ime_allowed(self.resources.input_handler.is_text_mode());
+
         let renderer = Renderer::new(
             window,
             Arc::clone(&self.resources.vfs),
@@ -149,9 +279,74 @@ impl ApplicationHandler for Runtime {
             .on_window_event(&mut self.resources, event_loop, event);
     }
 
+    fn device_event(
+        &mut self,
+        _event_loop: &ActiveEventLoop,
+        _device_id: DeviceId,
+        event: DeviceEvent,
+    ) {
+        self.event_handler
+            .on_device_event(&mut self.resources, event);
+    }
+
+    fn exiting(&mut self, _event_loop: &ActiveEventLoop) {
+        for context in self.resources.scripts.iter_mut() {
+            if let Some(report) = context.take_profile_report() {
+                info!("script profile of {}:\n{}", context.name(), report);
+            }
+        }
+
+        if let Some(recording) = self.resources.input_handler.stop_recording() {
+            let path = self.resources.config.input.record.as_str();
+
+            match recording.save(&self.resources.vfs, path) {
+                Ok(()) => info!("input recorded to {}", path),
+                Err(err) => error!("unable to save input recording {}: {}", path, err),
+            }
+        }
+    }
+
     fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
         self.event_handler
             .on_update(event_loop, &mut self.resources);
         self.event_handler.on_render(&mut self.resources);
     }
 }
+
+fn apply_cursor_mode(window: &Window, mode: CursorMode) {
+    let grab = match mode {
+        CursorMode::Normal | CursorMode::Hidden => CursorGrabMode::None,
+        CursorMode::Confined => CursorGrabMode::Confined,
+        CursorMode::Locked => CursorGrabMode::Locked,
+    };
+
+    // Not every platform supports both grab modes, fall back to the other one.
+    let result = window.set_cursor_grab(grab).or_else(|err| match grab {
+        CursorGrabMode::Locked => window.set_cursor_grab(CursorGrabMode::Confined),
+        CursorGrabMode::Confined => window.set_cursor_grab(CursorGrabMode::Locked),
+        CursorGrabMode::None => Err(err),
+    });
+
+    if let Err(err) = result {
+        warn!("unable to set cursor mode {:?}: {}", mode, err);
+    }
+
+    window.set_cursor_visible(matches!(mode, CursorMode::Normal | CursorMode::Confined));
+}
+
+fn load_input_map(vfs: &VirtualFs) -> InputMap {
+    for path in [USER_INPUT_MAP, DEFAULT_INPUT_MAP] {
+        if !vfs.exists(path).unwrap_or(false) {
+            continue;
+        }
+
+        match InputMap::load(vfs, path) {
+            Ok(map) => return map,
+            Err(err) => error!("unable to load input map {}: {}", path, err),
+        }
+    }
+
+    warn!("no input map loaded, nothing is bound");
+
+    InputMap::new()
+}
@@ -1,5 +1,5 @@
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Component, Path, PathBuf};
 
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
@@ -9,18 +9,62 @@ pub enum Error {
         error: std::io::Error,
     },
 
+    #[error("write error: {path_spec}: {error}")]
+    Write {
+        path_spec: String,
+        error: std::io::Error,
+    },
+
     #[error("Ill-formed path spec: {0}")]
     IllFormedPathSpec(String),
 
     #[error("Path prefix not found: {0}")]
     PathPrefixNotFound(String),
 
+    #[error("Path leaves its prefix: {0}")]
+    PathOutsidePrefix(String),
+
+    #[error("Permission denied: {path_spec}: {access} access is not allowed")]
+    PermissionDenied {
+        path_spec: String,
+        access: &'static str,
+    },
+
     #[error("i/o error: {0}")]
     Io(#[from] std::io::Error),
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Permissions {
+    pub read: bool,
+    pub write: bool,
+}
+
+impl Permissions {
+    pub const READ_ONLY: Self = Self {
+        read: true,
+        write: false,
+    };
+
+    pub const READ_WRITE: Self = Self {
+        read: true,
+        write: true,
+    };
+}
+
+#[derive(Debug, Clone)]
+pub struct DirEntry {
+    pub name: String,
+    pub is_dir: bool,
+}
+
+struct SearchPath {
+    path: PathBuf,
+    permissions: Permissions,
+}
+
 pub struct VirtualFs {
-    search_paths: HashMap<String, PathBuf>,
+    search_paths: HashMap<String, SearchPath>,
 }
 
 impl VirtualFs {
@@ -31,10 +75,26 @@ impl VirtualFs {
     }
 
     pub fn add_search_path(&mut self, prefix: impl Into<String>, path: PathBuf) {
-        self.search_paths.insert(prefix.into(), path);
+        self.add_search_path_with_permissions(prefix, path, Permissions::READ_ONLY);
+    }
+
+    pub fn add_search_path_with_permissions(
+        &mut self,
+        prefix: impl Into<String>,
+        path: PathBuf,
+        permissions: Permissions,
+    ) {
+        self.search_paths
+            .insert(prefix.into(), SearchPath { path, permissions });
     }
 
-    fn resolve_path_spec(&self, path: impl IntoPathSpec) -> Result<PathBuf, Error> {
+    pub fn permissions(&self, prefix: &str) -> Option<Permissions> {
+        self.search_paths
+            .get(prefix)
+            .map(|search_path| search_path.permissions)
+    }
+
+    fn resolve_path_spec(&self, path: impl IntoPathSpec) -> Result<(PathBuf, Permissions), Error> {
         let path_spec = path.as_path_spec()?;
 
         let (prefix, relative_path) = path_spec.split();
@@ -43,19 +103,86 @@ impl VirtualFs {
             .get(prefix)
             .ok_or_else(|| Error::PathPrefixNotFound(prefix.to_string()))?;
 
-        Ok(search_path.join(relative_path))
+        // Permissions are per prefix, so paths must stay under the directory of their prefix.
+        let escapes = Path::new(relative_path).components().any(|component| {
+            matches!(
+                component,
+                Component::ParentDir | Component::RootDir | Component::Prefix(_)
+            )
+        });
+
+        if escapes {
+            return Err(Error::PathOutsidePrefix(path_spec.to_string()));
+        }
+
+        Ok((
+            search_path.path.join(relative_path),
+            search_path.permissions,
+        ))
+    }
+
+    fn resolve_readable(&self, path_spec: PathSpec) -> Result<PathBuf, Error> {
+        let (path, permissions) = self.resolve_path_spec(path_spec)?;
+
+        if !permissions.read {
+            return Err(Error::PermissionDenied {
+                path_spec: path_spec.to_string(),
+                access: "read",
+            });
+        }
+
+        Ok(path)
+    }
+
+    fn resolve_writable(&self, path_spec: PathSpec) -> Result<PathBuf, Error> {
+        let (path, permissions) = self.resolve_path_spec(path_spec)?;
+
+        if !permissions.write {
+            return Err(Error::PermissionDenied {
+                path_spec: path_spec.to_string(),
+                access: "write",
+            });
+        }
+
+        Ok(path)
     }
 
     pub fn exists(&self, path: impl IntoPathSpec) -> Result<bool, Error> {
-        let path = self.resolve_path_spec(path)?;
+        let path = self.resolve_readable(path.as_path_spec()?)?;
 
         Ok(path.try_exists()?)
     }
 
+    pub fn read_dir(&self, path: impl IntoPathSpec) -> Result<Vec<DirEntry>, Error> {
+        let path_spec = path.as_path_spec()?;
+
+        let path = self.resolve_readable(path_spec)?;
+
+        let read_error = |error| Error::Read {
+            path_spec: path_spec.to_string(),
+            error,
+        };
+
+        let mut entries = Vec::new();
+
+        for entry in std::fs::read_dir(&path).map_err(read_error)? {
+            let entry = entry.map_err(read_error)?;
+
+            entries.push(DirEntry {
+                name: entry.file_name().to_string_lossy().into_owned(),
+                is_dir: entry.file_type().map_err(read_error)?.is_dir(),
+            });
+        }
+
+        entries.sort_by(|a, b| a.name.cmp(&b.name));
+
+        Ok(entries)
+    }
+
     pub fn read(&self, path: impl IntoPathSpec) -> Result<Vec<u8>, Error> {
         let path_spec = path.as_path_spec()?;
 
-        let path = self.resolve_path_spec(path_spec)?;
+        let path = self.resolve_readable(path_spec)?;
 
         let data = std::fs::read(&path).map_err(|error| Error::Read {
             path_spec: path_spec.to_string(),
@@ -64,6 +191,26 @@ impl VirtualFs {
 
         Ok(data)
     }
+
+    /// Writes a file, creating missing parent directories.
+    pub fn write(&self, path: impl IntoPathSpec, data: impl AsRef<[u8]>) -> Result<(), Error> {
+        let path_spec = path.as_path_spec()?;
+
+        let path = self.resolve_writable(path_spec)?;
+
+        let write_error = |error| Error::Write {
+            path_spec: path_spec.to_string(),
+            error,
+        };
+
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(write_error)?;
+        }
+
+        std::fs::write(&path, data).map_err(write_error)?;
+
+        Ok(())
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -120,3 +267,41 @@ impl std::fmt::Display for PathSpec<'_> {
         write!(f, "{}", self.0)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn user_vfs(name: &str) -> (VirtualFs, PathBuf) {
+        let root = std::env::temp_dir().join(format!("nechto-vfs-{}-{}", name, std::process::id()));
+        let user = root.join("user");
+        std::fs::create_dir_all(&user).unwrap();
+
+        let mut vfs = VirtualFs::new();
+        vfs.add_search_path_with_permissions("$user", user, Permissions::READ_WRITE);
+
+        (vfs, root)
+    }
+
+    #[test]
+    fn paths_stay_under_their_prefix() {
+        let (vfs, root) = user_vfs("escape");
+        std::fs::write(root.join("x"), "outside").unwrap();
+
+        for path in ["$user/../x", "$user/a/../../x", "$user//x"] {
+            assert!(matches!(vfs.read(path), Err(Error::PathOutsidePrefix(_))));
+            assert!(matches!(
+                vfs.write(path, "data"),
+                Err(Error::PathOutsidePrefix(_))
+            ));
+            assert!(matches!(vfs.exists(path), Err(Error::PathOutsidePrefix(_))));
+        }
+
+        assert_eq!(std::fs::read_to_string(root.join("x")).unwrap(), "outside");
+
+        vfs.write("$user/a/./x", "inside").unwrap();
+        assert_eq!(vfs.read("$user/a/x").unwrap(), b"inside");
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+}